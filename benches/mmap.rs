@@ -0,0 +1,55 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use find_binary_version::{version_from_path, version_mmap, BinaryKind};
+use std::{fs, path::PathBuf};
+
+// Size of the synthetic binary, which has no version, so it is scanned as a
+// whole
+const SIZE: usize = 200 * 1024 * 1024;
+
+/// Write a binary alternating printable strings and binary data, as found
+/// in firmware images.
+fn synthetic_binary() -> PathBuf {
+    let chunk: Vec<u8> = b"some printable string\0\x01\x02\xff\xfeanother one here\n"
+        .iter()
+        .copied()
+        .chain((0..=255).map(|b: u32| b as u8))
+        .collect();
+    let data: Vec<u8> = chunk.iter().copied().cycle().take(SIZE).collect();
+
+    let path = std::env::temp_dir().join("find-binary-version-mmap-bench.bin");
+    fs::write(&path, data).unwrap();
+    path
+}
+
+fn mmap(c: &mut Criterion) {
+    let path = synthetic_binary();
+    let kind = BinaryKind::custom("bench", r"Version (\d+\.\d+)");
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let mut group = c.benchmark_group("mmap");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(SIZE as u64));
+    group.bench_function("version_from_path 200 MiB", |b| {
+        b.iter(|| {
+            runtime
+                .block_on(version_from_path(&path, kind.clone()))
+                .unwrap()
+        })
+    });
+    group.bench_function("version_mmap 200 MiB", |b| {
+        b.iter(|| version_mmap(&path, kind.clone()).unwrap())
+    });
+    group.finish();
+
+    fs::remove_file(&path).unwrap();
+}
+
+criterion_group!(benches, mmap);
+criterion_main!(benches);
@@ -0,0 +1,46 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use find_binary_version::version_with_pattern_from_bytes;
+
+// Size of the synthetic binary, which has no version, so it is scanned as a
+// whole
+const SIZE: usize = 64 * 1024 * 1024;
+
+/// Build a binary alternating printable strings and binary data, as found
+/// in firmware images.
+fn synthetic_binary() -> Vec<u8> {
+    let chunk: Vec<u8> = b"some printable string\0\x01\x02\xff\xfeanother one here\n"
+        .iter()
+        .copied()
+        .chain((0..=255).map(|b: u32| b as u8))
+        .collect();
+    chunk.iter().copied().cycle().take(SIZE).collect()
+}
+
+fn strings(c: &mut Criterion) {
+    let data = synthetic_binary();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+
+    let mut group = c.benchmark_group("strings");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(SIZE as u64));
+    group.bench_function("version_with_pattern 64 MiB", |b| {
+        b.iter(|| {
+            runtime
+                .block_on(version_with_pattern_from_bytes(
+                    &data,
+                    r"Version (\d+\.\d+)",
+                ))
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, strings);
+criterion_main!(benches);
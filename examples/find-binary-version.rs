@@ -3,10 +3,13 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use anyhow::{format_err, Result};
-use find_binary_version::{version, version_with_pattern, BinaryKind};
-use std::path::PathBuf;
+use find_binary_version::{version, version_all, version_with_pattern, BinaryKind};
+use std::{io::SeekFrom, path::PathBuf};
 use structopt::StructOpt;
-use tokio::{fs::File, io::BufReader};
+use tokio::{
+    fs::File,
+    io::{AsyncSeekExt, BufReader},
+};
 
 #[derive(StructOpt, Debug)]
 #[structopt(name = "find-binary-version")]
@@ -16,6 +19,14 @@ struct Cli {
 
     /// Pattern to use to find the version
     pattern: Option<String>,
+
+    /// Output every version found, trying each known binary kind
+    #[structopt(long, conflicts_with = "pattern")]
+    all: bool,
+
+    /// Binary kind to use (e.g: u-boot or linux-kernel), auto by default
+    #[structopt(long, conflicts_with_all = &["pattern", "all"])]
+    kind: Option<BinaryKind>,
 }
 
 #[tokio::main]
@@ -24,14 +35,32 @@ async fn main() -> Result<()> {
 
     let mut input = BufReader::new(File::open(&cli.input).await?);
 
+    if cli.all {
+        let mut found = Vec::new();
+        for kind in BinaryKind::iter() {
+            input.seek(SeekFrom::Start(0)).await?;
+            for v in version_all(&mut input, kind.clone()).await? {
+                if !found.contains(&v) {
+                    println!("{}: {}", kind, v);
+                    found.push(v);
+                }
+            }
+        }
+
+        if found.is_empty() {
+            return Err(format_err!(
+                "{:?} does not has a known version information.",
+                cli.input
+            ));
+        }
+
+        return Ok(());
+    }
+
     let version = if let Some(pattern) = &cli.pattern {
-        version_with_pattern(&mut input, pattern).await
+        version_with_pattern(&mut input, pattern).await?
     } else {
-        version(&mut input, BinaryKind::UBoot).await.or(version(
-            &mut input,
-            BinaryKind::LinuxKernel,
-        )
-        .await)
+        version(&mut input, cli.kind.unwrap_or(BinaryKind::Auto)).await?
     };
 
     match version {
@@ -0,0 +1,181 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{linuxkernel::LinuxKernel, FindVersionError, VersionFinder};
+use std::io::{self, Cursor, SeekFrom};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+// Android boot image Magic Number
+pub(crate) const BOOT_MAGIC: &[u8; 8] = b"ANDROID!";
+
+// Page size used by the boot images from header version 3 onward
+const BOOT_IMAGE_V3_PAGE_SIZE: u32 = 0x1000;
+
+/// The fields of the boot image header needed to locate the kernel.
+struct BootHeader {
+    kernel_size: u32,
+    page_size: u32,
+    os_version: u32,
+}
+
+async fn read_boot_header<R: AsyncRead + AsyncSeek + Unpin>(
+    buf: &mut R,
+) -> io::Result<Option<BootHeader>> {
+    // Taken from: https://android.googlesource.com/platform/system/tools/mkbootimg/+/HEAD/include/bootimg/bootimg.h
+    //
+    // Header versions 0 to 2:
+    //
+    // Offset  Size  Name            Meaning
+    // 0000    8     magic           "ANDROID!"
+    // 0008    4     kernel_size     Size of the kernel, in bytes
+    // 000C    4     kernel_addr     Kernel load address
+    // ...
+    // 0024    4     page_size       Flash page size, the kernel starts at the second page
+    // 0028    4     header_version  Version of the header (unused before version 1)
+    // 002C    4     os_version      Android version and security patch level
+    //
+    // Header versions 3 and 4, using 4096 bytes pages:
+    //
+    // Offset  Size  Name            Meaning
+    // 0000    8     magic           "ANDROID!"
+    // 0008    4     kernel_size     Size of the kernel, in bytes
+    // 000C    4     ramdisk_size    Size of the ramdisk, in bytes
+    // 0010    4     os_version      Android version and security patch level
+    // ...
+    // 0028    4     header_version  Version of the header
+    buf.seek(SeekFrom::Start(0)).await?;
+    let mut header = [0; 0x30];
+    buf.read_exact(&mut header).await?;
+    if !header.starts_with(BOOT_MAGIC) {
+        return Ok(None);
+    }
+
+    let u32_at = |offset: usize| u32::from_le_bytes(header[offset..offset + 4].try_into().unwrap());
+    let header_version = u32_at(0x28);
+    debug!("read Android boot image header version {}", header_version);
+
+    Ok(Some(if header_version >= 3 {
+        BootHeader {
+            kernel_size: u32_at(0x08),
+            page_size: BOOT_IMAGE_V3_PAGE_SIZE,
+            os_version: u32_at(0x10),
+        }
+    } else {
+        BootHeader {
+            kernel_size: u32_at(0x08),
+            page_size: u32_at(0x24),
+            os_version: u32_at(0x2C),
+        }
+    }))
+}
+
+/// Read the Android version of the boot image, from the os_version field of
+/// its header.
+pub(crate) async fn read_os_version<R: AsyncRead + AsyncSeek + Unpin>(
+    buf: &mut R,
+) -> io::Result<Option<String>> {
+    let header = match read_boot_header(buf).await? {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+
+    // The version is stored as 7 bits for each of its A.B.C components,
+    // followed by 11 bits for the security patch level.
+    let version = header.os_version >> 11;
+    if version == 0 {
+        return Ok(None);
+    }
+
+    Ok(Some(format!(
+        "{}.{}.{}",
+        version >> 14,
+        (version >> 7) & 0x7F,
+        version & 0x7F
+    )))
+}
+
+pub(crate) struct AndroidBoot<'a, R: AsyncRead + AsyncSeek + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> AndroidBoot<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        AndroidBoot { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> VersionFinder for AndroidBoot<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        let header = match read_boot_header(self.buf).await? {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+
+        // The kernel starts at the page following the header. It is read
+        // as the Linux kernel finders look for its headers from the begin
+        // of the reader.
+        self.buf
+            .seek(SeekFrom::Start(header.page_size.into()))
+            .await?;
+        let mut kernel = Vec::new();
+        (&mut *self.buf)
+            .take(header.kernel_size.into())
+            .read_to_end(&mut kernel)
+            .await?;
+
+        LinuxKernel::from_reader(&mut Cursor::new(kernel))
+            .get_version()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{android_boot_os_version, version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("android/boot-v2.img", Some("5.10.0-9-arm64")),
+            ("android/boot-v4.img", Some("4.1.30-1-MANJARO")),
+            ("android/payload.bin", None),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::AndroidBoot)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn os_version() {
+        for (f, v) in &[
+            ("android/boot-v2.img", Some("11.0.0")),
+            ("android/boot-v4.img", Some("13.0.0")),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                android_boot_os_version(&mut fixture(f).await)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+}
@@ -0,0 +1,99 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{strings::IntoStringsIter, FindVersionError, VersionFinder};
+use regex::Regex;
+use std::io::SeekFrom;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+// Android OTA payload Magic Number
+pub(crate) const PAYLOAD_MAGIC: &[u8; 4] = b"CrAU";
+
+pub(crate) struct AndroidOtaPayload<'a, R: AsyncRead + AsyncSeek + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> AndroidOtaPayload<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        AndroidOtaPayload { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> VersionFinder for AndroidOtaPayload<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        // Taken from: https://android.googlesource.com/platform/system/update_engine/+/HEAD/update_metadata.proto
+        //
+        // Offset  Size  Name                     Meaning
+        // 0000    4     magic                    "CrAU"
+        // 0004    8     file_format_version      Payload major version
+        // 000C    8     manifest_size            Size of the manifest, in bytes
+        // 0014    4     metadata_signature_size  Only present if version >= 2
+        // ...     ...   manifest                 Protobuf DeltaArchiveManifest
+        self.buf.seek(SeekFrom::Start(0)).await?;
+        let mut magic = [0; 4];
+        self.buf.read_exact(&mut magic).await?;
+        if &magic != PAYLOAD_MAGIC {
+            return Ok(None);
+        }
+
+        let file_format_version = self.buf.read_u64().await?;
+        let manifest_size = self.buf.read_u64().await?;
+        if file_format_version >= 2 {
+            let _metadata_signature_size = self.buf.read_u32().await?;
+        }
+
+        // The manifest is encoded as protobuf, so the build fingerprint is
+        // stored as a length prefixed string and can be found among the
+        // printable strings. The shortest possible fingerprint is 'a/b/c'.
+        let mut manifest = Vec::new();
+        (&mut *self.buf)
+            .take(manifest_size)
+            .read_to_end(&mut manifest)
+            .await?;
+
+        let re = Regex::new(r"(?P<version>[A-Za-z0-9]+/[A-Za-z0-9_]+/[A-Za-z0-9_:.]+)").unwrap();
+        for line in manifest
+            .into_strings_iter()
+            .with_context_hint(5, usize::MAX)
+        {
+            if let Some(v) = re.captures(&line).and_then(|c| c.name("version")) {
+                return Ok(Some(v.as_str().to_string()));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("android/payload.bin", Some("google/coral/coral:11")),
+            ("uboot/arm-u-boot-dtb.img", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::AndroidOtaPayload)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use compress_tools::{list_archive_files, tokio_support, uncompress_archive_file};
+use std::io::Cursor;
+use tokio::io::AsyncRead;
+
+// Magic numbers of the compression formats archives are streamed through
+// (gzip, bzip2, xz, zstd and lz4). The archive is then only read forwards, as
+// opposed to formats requiring seeking (e.g: ZIP, whose members are found
+// through a central directory) or to libarchive probing uncompressed data.
+const STREAMABLE_MAGICS: [&[u8]; 5] = [
+    b"\x1f\x8b",
+    b"BZh",
+    b"\xfd7zXZ\x00",
+    b"\x28\xb5\x2f\xfd",
+    b"\x04\x22\x4d\x18",
+];
+
+// Length of the longest magic number of the streamable formats
+pub(crate) const MAGIC_SIZE: usize = 6;
+
+/// Whether the archive starting with the given bytes can be streamed.
+pub(crate) fn is_streamable(magic: &[u8]) -> bool {
+    STREAMABLE_MAGICS.iter().any(|m| magic.starts_with(m))
+}
+
+/// Extract the archive member at the given path, streaming the archive from
+/// the reader.
+pub(crate) async fn stream_archive_file<R: AsyncRead + Unpin>(
+    buffer: &mut R,
+    path: &str,
+) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+    tokio_support::uncompress_archive_file(buffer, &mut data, path)
+        .await
+        .ok()?;
+
+    Some(data)
+}
+
+/// Extract the first archive member whose path matches the given predicate.
+///
+/// The archive is already in memory, and some formats (e.g: ar and ZIP)
+/// require seeking, so the blocking API is used.
+pub(crate) fn read_archive_file<F: Fn(&str) -> bool>(
+    archive: &[u8],
+    matches: F,
+) -> Option<Vec<u8>> {
+    let path = list_archive_files(Cursor::new(archive))
+        .ok()?
+        .into_iter()
+        .find(|f| matches(f))?;
+
+    let mut data = Vec::new();
+    uncompress_archive_file(Cursor::new(archive), &mut data, &path).ok()?;
+
+    Some(data)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version_from_archive, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn from_archive() {
+        for (f, path, v) in &[
+            (
+                "archive/u-boot.tar.gz",
+                "firmware/u-boot-spl.bin",
+                Some("2017.11+fslc+ga07698f"),
+            ),
+            ("archive/u-boot.tar.gz", "firmware/README", None),
+            // Not compressed, so read in memory first
+            (
+                "archive/u-boot.tar",
+                "firmware/u-boot-spl.bin",
+                Some("2017.11+fslc+ga07698f"),
+            ),
+            ("archive/u-boot.tar.gz", "firmware/missing.bin", None),
+            ("uboot/arm-spl", "firmware/u-boot-spl.bin", None),
+        ] {
+            assert_eq!(
+                version_from_archive(&mut fixture(f).await, path, BinaryKind::UBoot)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+                "{} {}",
+                f,
+                path
+            );
+        }
+    }
+}
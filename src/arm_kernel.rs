@@ -0,0 +1,191 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    linuxkernel::{discover_linux_kernel_kind, read_uimage_version, LinuxKernelKind, IH_ARCH_ARM},
+    FindVersionError, VersionFinder,
+};
+use regex::bytes::Regex;
+use std::{
+    io::{self, SeekFrom},
+    str,
+};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+// Size of each read looking for the compressed kernel
+const ZIMAGE_CHUNK_SIZE: usize = 0x200;
+
+// Length of the longest compression format header (xz)
+const HEADER_SIZE: usize = 6;
+
+/// Look for the compressed kernel inside of a self-decompressing image (ARM
+/// zImage or MIPS vmlinuz), starting from the current reader position, and
+/// read the version from the uncompressed data.
+pub(crate) async fn read_zimage_version<R: AsyncRead + AsyncSeek + Unpin>(
+    buf: &mut R,
+) -> io::Result<Option<String>> {
+    async fn get_version_from_arm<R: AsyncRead + Unpin>(mut rd: R) -> Option<String> {
+        let mut buffer = Vec::default();
+        compress_tools::tokio_support::uncompress_data(&mut rd, &mut buffer)
+            .await
+            .ok()?;
+        let re = Regex::new(r"Linux version (?P<version>\S+).*").unwrap();
+        re.captures(&buffer)
+            .and_then(|m| m.name("version"))
+            .and_then(|v| str::from_utf8(v.as_bytes()).ok())
+            .map(|v| v.to_string())
+    }
+
+    // Each read fills the buffer after the bytes kept from the previous one,
+    // so headers starting in its last bytes are matched as a whole.
+    let mut buffer = [0; ZIMAGE_CHUNK_SIZE + HEADER_SIZE - 1];
+    let mut kept = 0;
+    loop {
+        let n = buf.read(&mut buffer[kept..]).await?;
+
+        // No more data to read
+        if n == 0 {
+            return Ok(None);
+        }
+        let len = kept + n;
+
+        // Look for compression format header
+        for (offset, window) in buffer[..len].windows(HEADER_SIZE).enumerate() {
+            // Headers taken from:
+            // https://github.com/torvalds/linux/blob/master/scripts/extract-vmlinux
+            match window {
+                [0x1f, 0x8b, 0x08, ..] => {}               // gzip
+                [0xfd, b'7', b'z', b'X', b'Z', 0x00] => {} // xz
+                [b'B', b'Z', b'h', ..] => {}               // bzip2
+                [0x5d, 0x00, ..] => {}                     // lzma
+                [0x89, 0x4c, 0x5a, ..] => {}               // lzo
+                [0x02, b'!', b'L', 0x18, ..] => {}         // lz4
+                [b'(', 0xb5, b'/', 0xfd, ..] => {}         // zstd
+                _ => continue,
+            }
+
+            let mut slice = &buffer[offset..len];
+            let current = buf.seek(SeekFrom::Current(0)).await?;
+            let rd = AsyncReadExt::chain(&mut slice, &mut *buf);
+
+            // Try to get version from uncompressed data
+            if let Some(version) = get_version_from_arm(rd).await {
+                return Ok(Some(version));
+            }
+
+            // Seek back to current position so we can keep looking
+            // for the next compression header
+            buf.seek(SeekFrom::Start(current)).await?;
+        }
+
+        // The bytes whose window was not looked at yet are kept for the next
+        // read, as a header may start among them
+        kept = len.min(HEADER_SIZE - 1);
+        buffer.copy_within(len - kept..len, 0);
+    }
+}
+
+pub(crate) struct ArmKernel<'a, R: AsyncRead + AsyncSeek + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> ArmKernel<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        ArmKernel { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> VersionFinder for ArmKernel<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        Ok(match discover_linux_kernel_kind(self.buf).await? {
+            Some(LinuxKernelKind::ARMzImage) => read_zimage_version(self.buf).await?,
+
+            Some(LinuxKernelKind::UImage) => {
+                // Only accept uImages built for ARM, checking the ih_arch field
+                self.buf.seek(SeekFrom::Start(0x001D)).await?;
+                if self.buf.read_u8().await? != IH_ARCH_ARM {
+                    return Ok(None);
+                }
+
+                read_uimage_version(self.buf).await?
+            }
+
+            Some(
+                LinuxKernelKind::X86bzImage
+                | LinuxKernelKind::X86zImage
+                | LinuxKernelKind::AArch64Image
+                | LinuxKernelKind::RISCVImage
+                | LinuxKernelKind::PowerPC
+                | LinuxKernelKind::MIPS,
+            )
+            | None => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/linuxkernel/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn header_across_reads() {
+        use super::read_zimage_version;
+
+        // The gzip header starts in the last bytes of the first read
+        let data = std::fs::read("tests/fixtures/linuxkernel/zimage-gzip-boundary.bin").unwrap();
+        assert_eq!(
+            read_zimage_version(&mut std::io::Cursor::new(data))
+                .await
+                .unwrap(),
+            Some("6.1.55".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn lzma_small_dictionary() {
+        use super::read_zimage_version;
+
+        // LZMA-alone data using a 32 KiB dictionary ("5d 00 80 00 00")
+        let data = std::fs::read("tests/fixtures/linuxkernel/zimage-lzma-small-dict.bin").unwrap();
+        assert_eq!(
+            read_zimage_version(&mut std::io::Cursor::new(data))
+                .await
+                .unwrap(),
+            Some("5.15.71-vendor".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn arm_version() {
+        for (f, v) in &[
+            ("arm-uImage", Some("4.1.15-1.2.0+g274a055")),
+            ("arm-zImage", Some("4.4.1")),
+            ("arm64-Image", None),
+            ("riscv64-Image", None),
+            ("ppc-vmlinux.strip", None),
+            ("mips-vmlinuz", None),
+            ("x86-bzImage", None),
+            ("x86-zImage", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::ArmKernel)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string())
+            );
+        }
+    }
+}
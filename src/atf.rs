@@ -0,0 +1,120 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{strings::IntoStringsIter, FindVersionError, VersionFinder};
+use regex::Regex;
+use std::io::SeekFrom;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+// Firmware Image Package (FIP) table of contents Magic Number
+pub(crate) const FIP_TOC_HEADER_NAME: u32 = 0xAA64_0001;
+
+fn find_version(data: &[u8]) -> Option<String> {
+    // The version string is followed by the build type (e.g:
+    // "v2.6(release):v2.6") or printed as a word of its own.
+    let re = Regex::new(r"(?:^|\W)v(?P<version>\d+\.\d+(?:\.\d+)?(?:-rc\d+)?)(?:\s|\(|$)").unwrap();
+    data.into_strings_iter().find_map(|s| {
+        re.captures(&s)
+            .and_then(|c| c.name("version"))
+            .map(|v| v.as_str().to_string())
+    })
+}
+
+pub(crate) struct ArmTrustedFirmware<'a, R: AsyncRead + AsyncSeek + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> ArmTrustedFirmware<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        ArmTrustedFirmware { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> VersionFinder for ArmTrustedFirmware<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        // Taken from: https://trustedfirmware-a.readthedocs.io/en/latest/design/firmware-design.html#firmware-image-package-fip
+        //
+        // Offset  Size  Name           Meaning
+        // 0000    4     name           ToC Magic Number, little endian
+        // 0004    4     serial_number  Non-zero number provided by the creation tool
+        // 0008    8     flags          Flags associated with this data
+        // 0010    40    entries        ToC entries, ended by an entry with a null UUID
+        //
+        // Each ToC entry is laid out as:
+        //   uuid (16), offset_address (8), size (8), flags (8)
+        self.buf.seek(SeekFrom::Start(0)).await?;
+        if self.buf.read_u32_le().await? != FIP_TOC_HEADER_NAME {
+            // Not a FIP, so a single firmware image (e.g: bl31.bin)
+            self.buf.seek(SeekFrom::Start(0)).await?;
+            let mut data = Vec::new();
+            self.buf.read_to_end(&mut data).await?;
+            return Ok(find_version(&data));
+        }
+
+        self.buf.seek(SeekFrom::Start(0x10)).await?;
+        let mut images = Vec::new();
+        loop {
+            let mut uuid = [0; 16];
+            self.buf.read_exact(&mut uuid).await?;
+            let offset = self.buf.read_u64_le().await?;
+            let size = self.buf.read_u64_le().await?;
+            let _flags = self.buf.read_u64_le().await?;
+            if uuid == [0; 16] {
+                break;
+            }
+
+            images.push((offset, size));
+        }
+        debug!(
+            "read {} images from the FIP table of contents",
+            images.len()
+        );
+
+        // The images are looked at in the ToC order, which lists the
+        // trusted firmware (BL2 and BL31) before the non-trusted one (BL33)
+        for (offset, size) in images {
+            self.buf.seek(SeekFrom::Start(offset)).await?;
+            let mut data = Vec::new();
+            (&mut *self.buf).take(size).read_to_end(&mut data).await?;
+            if let Some(version) = find_version(&data) {
+                return Ok(Some(version));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("atf/bl31.bin", Some("2.6")),
+            ("atf/fip.bin", Some("2.8-rc0")),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::ArmTrustedFirmware)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+}
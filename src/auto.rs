@@ -0,0 +1,293 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    builder::Config, busybox::Busybox, error, linuxkernel::LinuxKernel, uboot::UBoot, BinaryKind,
+    FindVersionError, VersionFinder, VersionInfo,
+};
+use std::io::SeekFrom;
+use tokio::io::{AsyncRead, AsyncSeek, AsyncSeekExt};
+
+pub(crate) struct Auto<'a, R: AsyncRead + AsyncSeek + Unpin> {
+    buf: &'a mut R,
+    config: Config<'a>,
+}
+
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> Auto<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        Auto {
+            buf,
+            config: Config::default(),
+        }
+    }
+
+    pub(crate) fn with_config(self, config: Config<'a>) -> Self {
+        Auto { config, ..self }
+    }
+
+    /// Get the version along with the kind which provided it.
+    pub(crate) async fn get_version_info(
+        &mut self,
+    ) -> Result<Option<VersionInfo>, FindVersionError> {
+        // Each kind is tried from the begin of the file, as the previous
+        // attempt may have consumed part of it, and within the timeout on its
+        // own.
+        self.buf.seek(SeekFrom::Start(0)).await?;
+        if let Some(version) = error::eof_as_not_found(
+            self.config
+                .with_timeout(
+                    UBoot::from_reader(self.buf)
+                        .with_config(self.config)
+                        .get_version(),
+                )
+                .await,
+        )? {
+            return Ok(Some(VersionInfo {
+                kind: BinaryKind::UBoot,
+                version,
+                offset: None,
+            }));
+        }
+
+        self.buf.seek(SeekFrom::Start(0)).await?;
+        if let Some(version) = error::eof_as_not_found(
+            self.config
+                .with_timeout(
+                    LinuxKernel::from_reader(self.buf)
+                        .with_config(self.config)
+                        .get_version(),
+                )
+                .await,
+        )? {
+            return Ok(Some(VersionInfo {
+                kind: BinaryKind::LinuxKernel,
+                version,
+                offset: None,
+            }));
+        }
+
+        self.buf.seek(SeekFrom::Start(0)).await?;
+        Ok(self
+            .config
+            .with_timeout(
+                Busybox::from_reader(self.buf)
+                    .with_config(self.config)
+                    .get_version(),
+            )
+            .await?
+            .map(|version| VersionInfo {
+                kind: BinaryKind::Busybox,
+                version,
+                offset: None,
+            }))
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> VersionFinder for Auto<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        Ok(self.get_version_info().await?.map(|info| info.version))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("uboot/arm-spl", Some("2017.11+fslc+ga07698f")),
+            ("linuxkernel/x86-bzImage", Some("4.1.30-1-MANJARO")),
+            ("linuxkernel/arm64-Image", Some("5.10.0-9-arm64")),
+            ("busybox/busybox", Some("1.36.1")),
+            ("helm/nginx-9.4.1.tgz", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::Auto)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn timeout() {
+        use crate::version_with_timeout;
+        use std::{
+            io,
+            pin::Pin,
+            task::{Context, Poll},
+            time::Duration,
+        };
+        use tokio::io::ReadBuf;
+
+        // Reader whose first read never completes, as a finder stuck on a
+        // malformed binary, until it is moved for the next attempt
+        struct StuckOnce<R> {
+            inner: R,
+            stuck: Option<bool>,
+        }
+
+        impl<R: AsyncRead + Unpin> AsyncRead for StuckOnce<R> {
+            fn poll_read(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &mut ReadBuf<'_>,
+            ) -> Poll<io::Result<()>> {
+                match self.stuck {
+                    None | Some(true) => {
+                        self.stuck = Some(true);
+                        Poll::Pending
+                    }
+                    Some(false) => Pin::new(&mut self.inner).poll_read(cx, buf),
+                }
+            }
+        }
+
+        impl<R: AsyncSeek + Unpin> AsyncSeek for StuckOnce<R> {
+            fn start_seek(mut self: Pin<&mut Self>, position: io::SeekFrom) -> io::Result<()> {
+                if self.stuck.is_some() {
+                    self.stuck = Some(false);
+                }
+                Pin::new(&mut self.inner).start_seek(position)
+            }
+
+            fn poll_complete(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<io::Result<u64>> {
+                Pin::new(&mut self.inner).poll_complete(cx)
+            }
+        }
+
+        // The binary is kept in memory, as giving up on a file leaves its
+        // pending operation behind, making the next attempts fail
+        let data = std::fs::read("tests/fixtures/linuxkernel/arm64-Image").unwrap();
+        let timeout = Duration::from_millis(500);
+        let mut kernel = StuckOnce {
+            inner: io::Cursor::new(&data),
+            stuck: None,
+        };
+        assert_eq!(
+            version_with_timeout(&mut kernel, BinaryKind::LinuxKernel, timeout)
+                .await
+                .unwrap(),
+            None
+        );
+
+        // The U-Boot attempt times out, and the Linux kernel one is still
+        // tried within its own timeout
+        let mut kernel = StuckOnce {
+            inner: io::Cursor::new(&data),
+            stuck: None,
+        };
+        assert_eq!(
+            version_with_timeout(&mut kernel, BinaryKind::Auto, timeout)
+                .await
+                .unwrap(),
+            Some("5.10.0-9-arm64".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn fallback() {
+        use crate::version_with_fallback;
+
+        let mut kernel = fixture("linuxkernel/arm64-Image").await;
+        let attempts = version_with_fallback(
+            &mut kernel,
+            &[BinaryKind::UBoot, BinaryKind::LinuxKernel, BinaryKind::Auto],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            attempts
+                .iter()
+                .map(|(kind, v)| (kind.to_string(), v.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("u-boot".to_string(), None),
+                ("linux-kernel".to_string(), Some("5.10.0-9-arm64")),
+                ("auto".to_string(), Some("5.10.0-9-arm64")),
+            ]
+        );
+
+        let mut spl = fixture("uboot/arm-spl").await;
+        let attempts = version_with_fallback(&mut spl, &[BinaryKind::UBoot])
+            .await
+            .unwrap();
+        assert_eq!(attempts.len(), 1);
+        assert_eq!(attempts[0].1.as_deref(), Some("2017.11+fslc+ga07698f"));
+    }
+
+    #[tokio::test]
+    async fn any() {
+        use crate::{version_any, version_any_from_path};
+        use std::io::Cursor;
+
+        for (f, kinds, v) in [
+            (
+                "uboot/arm-spl",
+                &[BinaryKind::LinuxKernel, BinaryKind::UBoot][..],
+                Some("u-boot: 2017.11+fslc+ga07698f"),
+            ),
+            // Both kinds provide the version, so the first one wins
+            (
+                "linuxkernel/arm64-Image",
+                &[
+                    BinaryKind::custom("banner", r"Linux version (\S+)"),
+                    BinaryKind::LinuxKernel,
+                ][..],
+                Some("banner: 5.10.0-9-arm64"),
+            ),
+            (
+                "linuxkernel/arm64-Image",
+                &[BinaryKind::LinuxKernel, BinaryKind::Auto][..],
+                Some("linux-kernel: 5.10.0-9-arm64"),
+            ),
+            ("helm/nginx-9.4.1.tgz", &[BinaryKind::UBoot][..], None),
+            ("uboot/arm-spl", &[][..], None),
+        ] {
+            let path = format!("tests/fixtures/{}", f);
+            let data = std::fs::read(&path).unwrap();
+            assert_eq!(
+                version_any(Cursor::new(&data[..]), kinds)
+                    .await
+                    .unwrap()
+                    .map(|i| i.to_string()),
+                v.map(|v| v.to_string()),
+                "{}",
+                f
+            );
+            assert_eq!(
+                version_any_from_path(&path, kinds)
+                    .await
+                    .unwrap()
+                    .map(|i| i.to_string()),
+                v.map(|v| v.to_string()),
+                "{}",
+                f
+            );
+        }
+
+        assert!(
+            version_any_from_path("tests/fixtures/missing", &[BinaryKind::UBoot])
+                .await
+                .is_err()
+        );
+    }
+}
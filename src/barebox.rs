@@ -0,0 +1,121 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{builder::Config, custom::find_map_strings, FindVersionError, VersionFinder};
+use regex::Regex;
+use tokio::io::AsyncRead;
+
+// Linker symbol name of the Barebox version string
+const BAREBOX_VERSION_LANDMARK: &str = "barebox_version_string";
+
+pub(crate) struct Barebox<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+    config: Config<'a>,
+}
+
+impl<'a, R: AsyncRead + Unpin> Barebox<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        Barebox {
+            buf,
+            config: Config::default(),
+        }
+    }
+
+    pub(crate) fn with_config(self, config: Config<'a>) -> Self {
+        Barebox { config, ..self }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for Barebox<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        let re = Regex::new(r"barebox[- ](?P<version>\d{4}\.\d+(?:\.\d+)?[^\s\x00]*)").unwrap();
+        let find_version = |s: &str| {
+            re.captures(s)
+                .and_then(|m| m.name("version"))
+                .map(|v| v.as_str().to_string())
+        };
+
+        // When the symbol name is kept, the version string is the string
+        // following it. Otherwise the first version string found is used,
+        // so the binary is scanned to its end looking for the symbol name.
+        let mut after_landmark = false;
+        let mut fallback = None;
+        let version = find_map_strings(self.buf, &self.config, |line| {
+            if after_landmark {
+                after_landmark = false;
+                if let Some(version) = find_version(line) {
+                    return Some(version);
+                }
+            }
+
+            if line.ends_with(BAREBOX_VERSION_LANDMARK) {
+                debug!("found the Barebox version string symbol name");
+                after_landmark = true;
+            } else if fallback.is_none() {
+                fallback = find_version(line);
+            }
+            None
+        })
+        .await?;
+
+        Ok(version.or(fallback))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("barebox/arm-barebox.img", Some("2021.10.0")),
+            ("barebox/x86-barebox.bin", Some("2019.08.1")),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::Barebox)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+
+        let image = crate::helpers::make_barebox_image("2023.01.0");
+        assert_eq!(
+            version(&mut std::io::Cursor::new(image), BinaryKind::Barebox)
+                .await
+                .unwrap(),
+            Some("2023.01.0".to_string()),
+        );
+    }
+
+    #[tokio::test]
+    async fn landmark_preferred() {
+        // The string following the symbol name wins over a version string
+        // found before it, several reads earlier
+        let mut data = b"\0barebox-2020.01.0\0".to_vec();
+        data.resize(0x5000, 0);
+        data.extend_from_slice(b"barebox_version_string\0barebox 2023.01.0 #1\0");
+
+        assert_eq!(
+            version(&mut std::io::Cursor::new(data), BinaryKind::Barebox)
+                .await
+                .unwrap(),
+            Some("2023.01.0".to_string()),
+        );
+    }
+}
@@ -0,0 +1,166 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{version, version_at_offset, version_with_pattern, BinaryKind, FindVersionError};
+use std::{
+    io::{self, Read, Seek, SeekFrom},
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+
+/// Adapter exposing a blocking reader through the async I/O traits.
+///
+/// Each call blocks until completion, which is fine as it is only polled by
+/// the runtime owned by the blocking functions.
+struct Blocking<R> {
+    inner: R,
+    seek: Option<io::Result<u64>>,
+}
+
+impl<R: Read + Unpin> AsyncRead for Blocking<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let n = self.inner.read(buf.initialize_unfilled())?;
+        buf.advance(n);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<R: Seek + Unpin> AsyncSeek for Blocking<R> {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let result = self.inner.seek(position);
+        self.seek = Some(result);
+        Ok(())
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        match self.seek.take() {
+            Some(result) => Poll::Ready(result),
+            // No seek in progress, so report the current position
+            None => Poll::Ready(self.inner.stream_position()),
+        }
+    }
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("failed to create the runtime")
+        .block_on(future)
+}
+
+/// Get the version for a specific binary, blocking the current thread.
+///
+/// This must not be called from within an async runtime.
+pub fn version_sync<R: Read + Seek>(
+    buffer: &mut R,
+    kind: BinaryKind,
+) -> Result<Option<String>, FindVersionError> {
+    let mut buffer = Blocking {
+        inner: buffer,
+        seek: None,
+    };
+
+    block_on(version(&mut buffer, kind))
+}
+
+/// Get the version for a specific pattern, blocking the current thread.
+///
+/// This must not be called from within an async runtime.
+pub fn version_with_pattern_sync<R: Read>(
+    buffer: &mut R,
+    pattern: &str,
+) -> Result<Option<String>, FindVersionError> {
+    let mut buffer = Blocking {
+        inner: buffer,
+        seek: None,
+    };
+
+    block_on(version_with_pattern(&mut buffer, pattern))
+}
+
+/// Get the version stored at a known offset of the binary, blocking the
+/// current thread.
+///
+/// This must not be called from within an async runtime.
+pub fn version_at_offset_sync<R: Read + Seek>(
+    buffer: &mut R,
+    offset: u64,
+    max_len: usize,
+) -> Result<Option<String>, FindVersionError> {
+    let mut buffer = Blocking {
+        inner: buffer,
+        seek: None,
+    };
+
+    block_on(version_at_offset(&mut buffer, offset, max_len))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{fs, path::Path};
+
+    fn fixtures(dir: &Path, found: &mut Vec<String>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                fixtures(&path, found);
+            } else {
+                found.push(path.to_string_lossy().into_owned());
+            }
+        }
+    }
+
+    #[test]
+    fn same_as_async() {
+        let mut found = Vec::new();
+        fixtures(Path::new("tests/fixtures"), &mut found);
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        for f in &found {
+            for kind in BinaryKind::iter() {
+                let expected = rt.block_on(async {
+                    let mut file =
+                        tokio::io::BufReader::new(tokio::fs::File::open(f).await.unwrap());
+                    version(&mut file, kind.clone()).await.unwrap()
+                });
+
+                let mut file = io::BufReader::new(fs::File::open(f).unwrap());
+                assert_eq!(
+                    version_sync(&mut file, kind.clone()).unwrap(),
+                    expected,
+                    "{} as {:?}",
+                    f,
+                    kind
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn at_offset() {
+        let mut file = fs::File::open("tests/fixtures/uboot/arm-spl").unwrap();
+
+        assert_eq!(
+            version_at_offset_sync(&mut file, 0x075A, 21).unwrap(),
+            Some("2017.11+fslc+ga07698f".to_string())
+        );
+    }
+
+    #[test]
+    fn pattern() {
+        let mut file = fs::File::open("tests/fixtures/uboot/arm-spl").unwrap();
+
+        assert_eq!(
+            version_with_pattern_sync(&mut file, r"U-Boot(?: SPL)? (\d+.?\.[^\s]+)").unwrap(),
+            Some("2017.11+fslc+ga07698f".to_string())
+        );
+    }
+}
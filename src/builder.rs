@@ -0,0 +1,480 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{custom::Custom, version_with_config, BinaryKind, FindVersionError};
+use std::{
+    future::Future,
+    io::{self, SeekFrom},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::io::{AsyncRead, AsyncSeek, AsyncSeekExt, ReadBuf};
+
+/// Detection parameters shared by the finders.
+///
+/// Unset parameters fall back to the defaults of each finder.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Config<'a> {
+    pub(crate) buffer_size: Option<usize>,
+    pub(crate) uboot_pattern: Option<&'a str>,
+    pub(crate) kernel_pattern: Option<&'a str>,
+    pub(crate) firmware_pattern: Option<&'a str>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) duplicates: bool,
+    pub(crate) max_string_length: Option<usize>,
+}
+
+impl Config<'_> {
+    /// Look for the version within the configured timeout, if any, providing
+    /// no version once it expires.
+    pub(crate) async fn with_timeout<T>(
+        &self,
+        find: impl Future<Output = Result<Option<T>, FindVersionError>>,
+    ) -> Result<Option<T>, FindVersionError> {
+        let timeout = match self.timeout {
+            Some(timeout) => timeout,
+            None => return find.await,
+        };
+
+        tokio::time::timeout(timeout, find)
+            .await
+            .unwrap_or_else(|_| {
+                debug!("gave up looking for the version after {:?}", timeout);
+                Ok(None)
+            })
+    }
+}
+
+/// Builder for a version finder with custom detection parameters.
+///
+/// [`max_bytes`](Self::max_bytes) applies to every binary kind, while the
+/// other parameters only apply to the kinds using them:
+///
+/// * [`buffer_size`](Self::buffer_size) and
+///   [`max_string_length`](Self::max_string_length) apply to the kinds
+///   looking for a version string in the whole binary, which are
+///   [`BinaryKind::Custom`], [`BinaryKind::Barebox`], [`BinaryKind::Busybox`],
+///   [`BinaryKind::GlibcLibrary`], [`BinaryKind::MuslLibrary`],
+///   [`BinaryKind::OpenSSL`], [`BinaryKind::CortexMFirmware`] and
+///   [`BinaryKind::ZephyrRTOS`]. [`BinaryKind::UBoot`] only uses the buffer
+///   size, unless given a custom pattern, and [`BinaryKind::LinuxKernel`] only
+///   uses them with a custom pattern;
+/// * the custom patterns apply to their own kind, also when it is tried by
+///   [`BinaryKind::Auto`].
+///
+/// The other kinds read the structures of their binary format, whose sizes
+/// are set by the format.
+///
+/// ```no_run
+/// # async fn f() -> Result<(), find_binary_version::FindVersionError> {
+/// use find_binary_version::{BinaryKind, VersionFinderBuilder};
+///
+/// let finder = VersionFinderBuilder::new()
+///     .buffer_size(0x100)
+///     .max_bytes(0x10_0000)
+///     .build(BinaryKind::UBoot);
+///
+/// let mut file = tokio::io::BufReader::new(tokio::fs::File::open("u-boot.img").await?);
+/// let version = finder.version(&mut file).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct VersionFinderBuilder {
+    buffer_size: Option<usize>,
+    max_bytes: Option<u64>,
+    uboot_pattern: Option<String>,
+    kernel_pattern: Option<String>,
+    firmware_pattern: Option<String>,
+    with_duplicates: bool,
+    max_string_length: Option<usize>,
+}
+
+impl VersionFinderBuilder {
+    /// Create a builder using the default detection parameters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Size of each read done by the finders scanning the binary for the
+    /// version string (e.g: U-Boot and custom patterns).
+    ///
+    /// Version strings longer than half of the buffer may not be found.
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        self.buffer_size = Some(size.max(1));
+        self
+    }
+
+    /// Only look at the first `max_bytes` bytes of the binary.
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Regular expression used for [`BinaryKind::UBoot`], whose first
+    /// capture group is the version.
+    pub fn custom_uboot_pattern(mut self, pattern: &str) -> Self {
+        self.uboot_pattern = Some(pattern.to_string());
+        self
+    }
+
+    /// Regular expression used for [`BinaryKind::LinuxKernel`], whose first
+    /// capture group is the version.
+    ///
+    /// The kernel formats are still detected as usual, and the pattern is
+    /// looked for in the whole kernel image.
+    pub fn custom_kernel_pattern(mut self, pattern: &str) -> Self {
+        self.kernel_pattern = Some(pattern.to_string());
+        self
+    }
+
+    /// Regular expression used for [`BinaryKind::CortexMFirmware`], whose
+    /// first capture group is the version.
+    ///
+    /// Bare-metal firmware has no standard version string, so a pattern
+    /// matching the one of the firmware at hand is more reliable than the
+    /// default one.
+    pub fn custom_firmware_pattern(mut self, pattern: &str) -> Self {
+        self.firmware_pattern = Some(pattern.to_string());
+        self
+    }
+
+    /// Keep the versions found more than once when listing the versions of
+    /// a binary with [`ConfiguredFinder::version_first_n`], so each of them
+    /// counts against the number of versions to find.
+    pub fn with_duplicates(mut self, with_duplicates: bool) -> Self {
+        self.with_duplicates = with_duplicates;
+        self
+    }
+
+    /// Truncate the strings of the binary longer than `length` characters
+    /// before matching the patterns against them, as done when scanning the
+    /// binary for the version string (e.g: U-Boot and custom patterns).
+    ///
+    /// Long runs of printable bytes (e.g: base64 encoded blobs) make some
+    /// patterns very slow to match, so a cap of 1024 is recommended for the
+    /// patterns which are not meant to match long strings. The strings are
+    /// not truncated by default.
+    pub fn max_string_length(mut self, length: usize) -> Self {
+        self.max_string_length = Some(length);
+        self
+    }
+
+    /// Create the version finder for the given binary kind.
+    pub fn build(self, kind: BinaryKind) -> ConfiguredFinder {
+        ConfiguredFinder {
+            kind,
+            builder: self,
+        }
+    }
+}
+
+/// Version finder created by [`VersionFinderBuilder`].
+#[derive(Debug, Clone)]
+pub struct ConfiguredFinder {
+    kind: BinaryKind,
+    builder: VersionFinderBuilder,
+}
+
+impl ConfiguredFinder {
+    /// Get the version for the binary, as done by [`version`](crate::version).
+    pub async fn version<R: AsyncRead + AsyncSeek + Unpin>(
+        &self,
+        buffer: &mut R,
+    ) -> Result<Option<String>, FindVersionError> {
+        let config = self.config();
+        match self.builder.max_bytes {
+            Some(limit) => {
+                let mut buffer = Limit::new(buffer, limit).await?;
+                version_with_config(&mut buffer, self.kind.clone(), config).await
+            }
+            None => version_with_config(buffer, self.kind.clone(), config).await,
+        }
+    }
+
+    /// Get up to `n` distinct versions for the binary, as done by
+    /// [`version_first_n`](crate::version_first_n), keeping the duplicates
+    /// when enabled with [`VersionFinderBuilder::with_duplicates`].
+    ///
+    /// Only [`BinaryKind::Custom`] may provide more than one version, as the
+    /// other kinds provide their single version, if any.
+    pub async fn version_first_n<R: AsyncRead + AsyncSeek + Unpin>(
+        &self,
+        buffer: &mut R,
+        n: usize,
+    ) -> Result<Vec<String>, FindVersionError> {
+        let pattern = match &self.kind {
+            BinaryKind::Custom { pattern, .. } => pattern,
+            _ => {
+                let version = self.version(buffer).await?;
+                return Ok(version.into_iter().take(n).collect());
+            }
+        };
+
+        let config = self.config();
+        match self.builder.max_bytes {
+            Some(limit) => {
+                let mut buffer = Limit::new(buffer, limit).await?;
+                Custom::from_reader(&mut buffer, pattern.as_str())
+                    .with_config(config)
+                    .get_versions(n)
+                    .await
+            }
+            None => {
+                Custom::from_reader(buffer, pattern.as_str())
+                    .with_config(config)
+                    .get_versions(n)
+                    .await
+            }
+        }
+    }
+
+    fn config(&self) -> Config<'_> {
+        Config {
+            buffer_size: self.builder.buffer_size,
+            uboot_pattern: self.builder.uboot_pattern.as_deref(),
+            kernel_pattern: self.builder.kernel_pattern.as_deref(),
+            firmware_pattern: self.builder.firmware_pattern.as_deref(),
+            timeout: None,
+            duplicates: self.builder.with_duplicates,
+            max_string_length: self.builder.max_string_length,
+        }
+    }
+}
+
+/// Adapter reporting the end of file once the given offset is reached.
+struct Limit<R> {
+    inner: R,
+    pos: u64,
+    limit: u64,
+}
+
+impl<R: AsyncSeek + Unpin> Limit<R> {
+    async fn new(mut inner: R, limit: u64) -> Result<Self, FindVersionError> {
+        let pos = inner.stream_position().await?;
+        Ok(Limit { inner, pos, limit })
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for Limit<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let remaining = self.limit.saturating_sub(self.pos);
+        let max = usize::try_from(remaining).map_or(buf.remaining(), |r| r.min(buf.remaining()));
+        if max == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        let mut limited = ReadBuf::new(buf.initialize_unfilled_to(max));
+        match Pin::new(&mut self.inner).poll_read(cx, &mut limited) {
+            Poll::Ready(Ok(())) => {
+                let n = limited.filled().len();
+                buf.advance(n);
+                self.pos += n as u64;
+                Poll::Ready(Ok(()))
+            }
+            poll => poll,
+        }
+    }
+}
+
+impl<R: AsyncSeek + Unpin> AsyncSeek for Limit<R> {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        Pin::new(&mut self.inner).start_seek(position)
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let poll = Pin::new(&mut self.inner).poll_complete(cx);
+        if let Poll::Ready(Ok(pos)) = poll {
+            self.pos = pos;
+        }
+        poll
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{BinaryKind, VersionFinderBuilder};
+    use std::io::Cursor;
+
+    const UBOOT: &[u8] = b"U-Boot 2023.10-rc3 (Oct 01 2023 - 10:00:00 +0000)\0";
+
+    fn synthetic(offset: usize) -> Cursor<Vec<u8>> {
+        let mut data = vec![0; offset];
+        data.extend_from_slice(UBOOT);
+        data.extend_from_slice(&[0; 0x10]);
+        Cursor::new(data)
+    }
+
+    #[tokio::test]
+    async fn buffer_size() {
+        for size in [0x80, 0x100, 0x1000] {
+            for kind in [
+                BinaryKind::UBoot,
+                BinaryKind::custom("u-boot", r"U-Boot (\S+)"),
+            ] {
+                assert_eq!(
+                    VersionFinderBuilder::new()
+                        .buffer_size(size)
+                        .build(kind)
+                        .version(&mut synthetic(0x7b))
+                        .await
+                        .unwrap(),
+                    Some("2023.10-rc3".to_string()),
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn max_bytes() {
+        let finder = VersionFinderBuilder::new()
+            .max_bytes(0x100)
+            .build(BinaryKind::UBoot);
+
+        assert_eq!(
+            finder.version(&mut synthetic(0x20)).await.unwrap(),
+            Some("2023.10-rc3".to_string()),
+        );
+        assert_eq!(finder.version(&mut synthetic(0xf0)).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn limit() {
+        use crate::{version_with_limit, version_with_pattern_with_limit};
+
+        for (offset, v) in [(0x20, Some("2023.10-rc3")), (0x100, None)] {
+            assert_eq!(
+                version_with_limit(&mut synthetic(offset), BinaryKind::UBoot, 0x100)
+                    .await
+                    .unwrap()
+                    .as_deref(),
+                v,
+            );
+            assert_eq!(
+                version_with_pattern_with_limit(&mut synthetic(offset), r"U-Boot (\S+)", 0x100)
+                    .await
+                    .unwrap()
+                    .as_deref(),
+                v,
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn uboot_pattern() {
+        assert_eq!(
+            VersionFinderBuilder::new()
+                .custom_uboot_pattern(r"U-Boot (\d+)\.")
+                .build(BinaryKind::UBoot)
+                .version(&mut synthetic(0x20))
+                .await
+                .unwrap(),
+            Some("2023".to_string()),
+        );
+    }
+
+    #[tokio::test]
+    async fn kernel_pattern() {
+        let finder = VersionFinderBuilder::new()
+            .custom_kernel_pattern(r"(\d+\.\d+\.\d+)-1-MANJARO")
+            .build(BinaryKind::LinuxKernel);
+
+        let kernel = std::fs::read("tests/fixtures/linuxkernel/x86-bzImage").unwrap();
+        assert_eq!(
+            finder.version(&mut Cursor::new(kernel)).await.unwrap(),
+            Some("4.1.30".to_string()),
+        );
+
+        // The pattern is only used for kernel images
+        let mut data = synthetic(0x20).into_inner();
+        data.extend_from_slice(b"4.1.30-1-MANJARO\0");
+        assert_eq!(finder.version(&mut Cursor::new(data)).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn first_n() {
+        let data = b"\0U-Boot 2023.10\0U-Boot 2023.10\0U-Boot 2024.01\0U-Boot 2024.04\0";
+        let kind = BinaryKind::custom("u-boot", r"U-Boot (\S+)");
+
+        for (with_duplicates, versions) in [
+            (false, ["2023.10", "2024.01"]),
+            (true, ["2023.10", "2023.10"]),
+        ] {
+            assert_eq!(
+                VersionFinderBuilder::new()
+                    .with_duplicates(with_duplicates)
+                    .build(kind.clone())
+                    .version_first_n(&mut Cursor::new(data), 2)
+                    .await
+                    .unwrap(),
+                versions,
+            );
+        }
+
+        // The other kinds provide a single version
+        assert_eq!(
+            VersionFinderBuilder::new()
+                .build(BinaryKind::UBoot)
+                .version_first_n(&mut synthetic(0x20), 2)
+                .await
+                .unwrap(),
+            ["2023.10-rc3"],
+        );
+    }
+
+    #[tokio::test]
+    async fn max_string_length() {
+        let mut data = vec![0];
+        data.extend_from_slice(&[b'A'; 0x800]);
+        data.extend_from_slice(b" version 1.2.3\0version 4.5.6\0");
+        let kind = BinaryKind::custom("blob", r"version (\S+)");
+
+        for (length, v) in [(None, "1.2.3"), (Some(1024), "4.5.6")] {
+            let mut builder = VersionFinderBuilder::new().buffer_size(0x1000);
+            if let Some(length) = length {
+                builder = builder.max_string_length(length);
+            }
+            assert_eq!(
+                builder
+                    .build(kind.clone())
+                    .version(&mut Cursor::new(&data))
+                    .await
+                    .unwrap()
+                    .as_deref(),
+                Some(v),
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn scanning_kinds() {
+        let mut data = vec![0];
+        data.extend_from_slice(&[b'A'; 0x800]);
+        data.extend_from_slice(b" BusyBox v1.36.1 multi-call binary.\0");
+
+        for (length, v) in [(None, Some("1.36.1")), (Some(1024), None)] {
+            let mut builder = VersionFinderBuilder::new().buffer_size(0x1000);
+            if let Some(length) = length {
+                builder = builder.max_string_length(length);
+            }
+            for kind in [BinaryKind::Busybox, BinaryKind::Auto] {
+                assert_eq!(
+                    builder
+                        .clone()
+                        .build(kind)
+                        .version(&mut Cursor::new(&data))
+                        .await
+                        .unwrap()
+                        .as_deref(),
+                    v,
+                );
+            }
+        }
+    }
+}
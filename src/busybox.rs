@@ -0,0 +1,82 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{builder::Config, custom::Custom, FindVersionError, VersionFinder};
+use tokio::io::AsyncRead;
+
+// Busybox version string, as printed in the usage banner (e.g: "BusyBox
+// v1.36.1 (2023-07-01 10:00:00 UTC) multi-call binary.")
+const BUSYBOX_VERSION_PATTERN: &str = r"BusyBox v(?P<version>\d+\.\d+\.\d+)";
+
+pub(crate) struct Busybox<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+    config: Config<'a>,
+}
+
+impl<'a, R: AsyncRead + Unpin> Busybox<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        Busybox {
+            buf,
+            config: Config::default(),
+        }
+    }
+
+    pub(crate) fn with_config(self, config: Config<'a>) -> Self {
+        Busybox { config, ..self }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for Busybox<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        // The banner is a plain string, so it is looked for in chunks as
+        // done for the custom patterns
+        Custom::from_reader(self.buf, BUSYBOX_VERSION_PATTERN)
+            .with_config(self.config)
+            .with_group("version")
+            .get_version()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[("busybox/busybox", Some("1.36.1")), ("uboot/arm-spl", None)] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::Busybox)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn at_start() {
+        assert_eq!(
+            version(
+                &mut std::io::Cursor::new(b"BusyBox v1.35.0 (2022-01-01)\0"),
+                BinaryKind::Busybox
+            )
+            .await
+            .unwrap(),
+            Some("1.35.0".to_string()),
+        );
+    }
+}
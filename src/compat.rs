@@ -0,0 +1,73 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{version, version_with_pattern, BinaryKind, FindVersionError};
+use futures_io::{AsyncRead, AsyncSeek};
+use tokio_util::compat::FuturesAsyncReadCompatExt;
+
+/// Get the version for a specific binary read through the `futures-io`
+/// traits, as done by [`version`](crate::version).
+///
+/// This is the reader of the async-std and smol runtimes (e.g:
+/// `async_std::fs::File`), which is adapted to the `tokio` I/O traits used
+/// by the finders. No `tokio` runtime is needed to poll it.
+pub async fn version_async_std<R: AsyncRead + AsyncSeek + Unpin>(
+    buffer: &mut R,
+    kind: BinaryKind,
+) -> Result<Option<String>, FindVersionError> {
+    version(&mut buffer.compat(), kind).await
+}
+
+/// Get the version for a binary read through the `futures-io` traits, using
+/// a custom pattern, as done by [`version_with_pattern`].
+pub async fn version_with_pattern_async_std<R: AsyncRead + AsyncSeek + Unpin>(
+    buffer: &mut R,
+    pattern: &str,
+) -> Result<Option<String>, FindVersionError> {
+    version_with_pattern(&mut buffer.compat(), pattern).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tokio_util::compat::TokioAsyncReadCompatExt;
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+        .compat()
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("uboot/arm-spl", Some("2017.11+fslc+ga07698f")),
+            ("linuxkernel/arm64-Image", None),
+        ] {
+            assert_eq!(
+                version_async_std(&mut fixture(f).await, BinaryKind::UBoot)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+                "{} version mismatch",
+                f
+            );
+        }
+
+        assert_eq!(
+            version_with_pattern_async_std(
+                &mut fixture("linuxkernel/arm64-Image").await,
+                r"Linux version (\S+)"
+            )
+            .await
+            .unwrap(),
+            Some("5.10.0-9-arm64".to_string())
+        );
+    }
+}
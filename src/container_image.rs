@@ -0,0 +1,137 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    archive::read_archive_file, strings::IntoStringsIter, FindVersionError, VersionFinder,
+};
+use regex::Regex;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+// Label used by the OCI image specification for the packaged software version
+#[cfg(feature = "json")]
+const OCI_VERSION_LABEL: &str = "org.opencontainers.image.version";
+
+/// Extract the image configuration from an image archive.
+///
+/// Archives created by `docker save` point to the configuration through the
+/// `manifest.json` file, while single layers may carry a `config.json` file.
+fn read_config(archive: &[u8]) -> Option<Vec<u8>> {
+    if let Some(manifest) = read_archive_file(archive, |f| f == "manifest.json") {
+        let re = Regex::new(r#""Config"\s*:\s*"(?P<config>[^"]+)""#).unwrap();
+        let manifest = String::from_utf8_lossy(&manifest);
+        let config = re.captures(&manifest)?.name("config")?.as_str();
+
+        return read_archive_file(archive, |f| f == config);
+    }
+
+    read_archive_file(archive, |f| f.trim_start_matches("./") == "config.json")
+}
+
+#[cfg(feature = "json")]
+fn find_label(config: &[u8]) -> Option<String> {
+    let config: serde_json::Value = serde_json::from_slice(config).ok()?;
+    config
+        .get("config")
+        .unwrap_or(&config)
+        .get("Labels")?
+        .get(OCI_VERSION_LABEL)?
+        .as_str()
+        .map(|v| v.to_string())
+}
+
+#[cfg(not(feature = "json"))]
+fn find_label(_config: &[u8]) -> Option<String> {
+    None
+}
+
+pub(crate) struct ContainerImage<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + Unpin> ContainerImage<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        ContainerImage { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for ContainerImage<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        // FIXME: Avoid reading the whole file
+        let mut buffer = Vec::new();
+        self.buf.read_to_end(&mut buffer).await?;
+
+        // Not being an archive, the input may be the configuration itself
+        let config = read_config(&buffer).unwrap_or(buffer);
+        if let Some(version) = find_label(&config) {
+            return Ok(Some(version));
+        }
+
+        // Without JSON support, or when the label is not set, look for the
+        // version in the raw configuration.
+        let patterns = [
+            Regex::new(r#""org\.opencontainers\.image\.version"\s*:\s*"(?P<version>[^"]+)""#)
+                .unwrap(),
+            Regex::new(r#""version"\s*:\s*"(?P<version>[^"]+)""#).unwrap(),
+        ];
+        for line in config.into_strings_iter() {
+            if let Some(v) = patterns
+                .iter()
+                .find_map(|re| re.captures(&line).and_then(|c| c.name("version")))
+            {
+                return Ok(Some(v.as_str().to_string()));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("container/image.tar.gz", Some("1.4.2")),
+            ("container/layer.tgz", Some("3.2.1")),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::ContainerImage)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn label() {
+        use super::find_label;
+
+        assert_eq!(
+            find_label(br#"{"config":{"Labels":{"org.opencontainers.image.version":"2.0"}}}"#),
+            Some("2.0".to_string())
+        );
+        assert_eq!(
+            find_label(br#"{"Labels":{"org.opencontainers.image.version":"2.1"}}"#),
+            Some("2.1".to_string())
+        );
+        assert_eq!(find_label(br#"{"Labels":{"version":"2.2"}}"#), None);
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{builder::Config, custom::Custom, FindVersionError, VersionFinder};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+// Version string commonly embedded in bare-metal firmware (e.g: "v1.4.2"),
+// used unless a pattern is configured
+const CORTEX_M_VERSION_PATTERN: &str = r"\bv(?P<version>\d+\.\d+\.\d+)";
+
+pub(crate) struct CortexMFirmware<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+    config: Config<'a>,
+}
+
+impl<'a, R: AsyncRead + Unpin> CortexMFirmware<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        CortexMFirmware {
+            buf,
+            config: Config::default(),
+        }
+    }
+
+    pub(crate) fn with_config(self, config: Config<'a>) -> Self {
+        CortexMFirmware { config, ..self }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for CortexMFirmware<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        // Taken from: ARMv7-M Architecture Reference Manual, B1.5.3
+        //
+        // Offset  Size  Name   Meaning
+        // 0000    4     SP     Initial value of the main stack pointer
+        // 0004    4     Reset  Address of the reset handler
+        // 0008    ...          Addresses of the other exception handlers
+        //
+        // The stack pointer is word aligned, and the handlers are Thumb code,
+        // so their addresses have the least significant bit set.
+        let sp = self.buf.read_u32_le().await?;
+        let reset = self.buf.read_u32_le().await?;
+        if sp == 0 || sp % 4 != 0 || reset & 1 == 0 || reset == u32::MAX {
+            return Ok(None);
+        }
+        debug!(
+            "read Cortex-M vector table (SP {:#010x}, reset {:#010x})",
+            sp, reset
+        );
+
+        // The version is held by the constant data (.rodata), so it is looked
+        // for in the whole image
+        match self.config.firmware_pattern {
+            Some(pattern) => {
+                Custom::from_reader(self.buf, pattern)
+                    .with_config(self.config)
+                    .get_version()
+                    .await
+            }
+            None => {
+                Custom::from_reader(self.buf, CORTEX_M_VERSION_PATTERN)
+                    .with_group("version")
+                    .with_config(self.config)
+                    .get_version()
+                    .await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind, VersionFinderBuilder};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("cortexm/firmware.bin", Some("2.3.1")),
+            ("cortexm/firmware-arm-mode.bin", None),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::CortexMFirmware)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+                "{} version mismatch",
+                f
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn firmware_pattern() {
+        assert_eq!(
+            VersionFinderBuilder::new()
+                .custom_firmware_pattern(r"FW_VERSION=(\d+\.\d+-\w+)")
+                .build(BinaryKind::CortexMFirmware)
+                .version(&mut fixture("cortexm/firmware.bin").await)
+                .await
+                .unwrap(),
+            Some("2.3-release".to_string()),
+        );
+    }
+}
@@ -2,51 +2,315 @@
 //
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::{strings::IntoStringsIter, VersionFinder};
-use regex::Regex;
+use crate::{
+    builder::Config,
+    strings::{IntoStringsIter, IsPrintable},
+    FindVersionError, VersionFinder,
+};
+use regex::{Captures, Regex};
+use std::{borrow::Cow, fmt};
 use tokio::io::{AsyncRead, AsyncReadExt};
 
-pub(crate) struct Custom<'a, R>
+// Size of each read, bounding the memory used to look for the version,
+// unless configured otherwise
+pub(crate) const CHUNK_SIZE: usize = 0x1000;
+
+/// Pattern to look for, either as given by the user or already compiled.
+#[derive(Debug, Clone, Copy)]
+pub enum Pattern<'a> {
+    /// Regular expression source, compiled when looking for the version.
+    Source(&'a str),
+    /// Compiled regular expression.
+    Compiled(&'a Regex),
+}
+
+impl<'a> Pattern<'a> {
+    fn regex(self) -> Result<Cow<'a, Regex>, regex::Error> {
+        match self {
+            Pattern::Source(pattern) => Regex::new(pattern).map(Cow::Owned),
+            Pattern::Compiled(re) => Ok(Cow::Borrowed(re)),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Pattern<'a> {
+    fn from(pattern: &'a str) -> Self {
+        Pattern::Source(pattern)
+    }
+}
+
+impl<'a> From<&'a Regex> for Pattern<'a> {
+    fn from(re: &'a Regex) -> Self {
+        Pattern::Compiled(re)
+    }
+}
+
+/// Regular expression looked for by [`Custom`], either a pattern or owned by
+/// the finder.
+#[derive(Debug)]
+enum Source<'a> {
+    Pattern(Pattern<'a>),
+    Regex(Regex),
+}
+
+impl<'a> Source<'a> {
+    fn regex(&self) -> Result<Cow<'_, Regex>, regex::Error> {
+        match self {
+            Source::Pattern(pattern) => pattern.regex(),
+            Source::Regex(re) => Ok(Cow::Borrowed(re)),
+        }
+    }
+
+    /// Compile the pattern, checking it has the given group.
+    fn regex_with_group(&self, group: Option<&str>) -> Result<Cow<'_, Regex>, FindVersionError> {
+        let re = self.regex()?;
+        if let Some(group) = group {
+            if !re.capture_names().flatten().any(|name| name == group) {
+                return Err(regex::Error::Syntax(format!(
+                    "capture group {:?} not found in {:?}",
+                    group,
+                    re.as_str()
+                ))
+                .into());
+            }
+        }
+
+        Ok(re)
+    }
+}
+
+/// Finder of the version matched by a regular expression, as used by
+/// [`version_with_pattern`](crate::version_with_pattern).
+///
+/// The version is the first capture group of the first match, unless another
+/// group is chosen with [`Custom::with_group`].
+///
+/// ```
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// use find_binary_version::{Custom, VersionFinder};
+///
+/// let mut binary = &b"\x00vendor firmware v4.2\x00"[..];
+/// let mut finder = Custom::from_reader(&mut binary, r"firmware v(?P<version>\S+)")
+///     .with_group("version");
+/// assert_eq!(finder.get_version().await.unwrap(), Some("4.2".to_string()));
+/// # });
+/// ```
+pub struct Custom<'a, R>
 where
     R: AsyncRead + Unpin,
 {
     buf: &'a mut R,
-    pattern: &'a str,
+    pattern: Source<'a>,
+    group: Option<&'a str>,
+    config: Config<'a>,
 }
 
 impl<'a, R> Custom<'a, R>
 where
     R: AsyncRead + Unpin,
 {
-    pub(crate) fn from_reader(buf: &'a mut R, pattern: &'a str) -> Self {
-        Custom { buf, pattern }
+    /// Create the finder looking for the pattern in the reader.
+    pub fn from_reader(buf: &'a mut R, pattern: impl Into<Pattern<'a>>) -> Self {
+        Custom {
+            buf,
+            pattern: Source::Pattern(pattern.into()),
+            group: None,
+            config: Config::default(),
+        }
+    }
+
+    /// Create the finder looking for the regular expression in the reader,
+    /// failing early when it is not valid.
+    ///
+    /// ```
+    /// use find_binary_version::{Custom, FindVersionError};
+    /// use regex::Regex;
+    ///
+    /// let mut binary = &b"\x00vendor firmware v4.2\x00"[..];
+    /// assert!(matches!(
+    ///     Custom::try_from_reader(&mut binary, Regex::new(r"firmware v(\S+")),
+    ///     Err(FindVersionError::InvalidPattern(_))
+    /// ));
+    /// ```
+    pub fn try_from_reader(
+        buf: &'a mut R,
+        re: Result<Regex, regex::Error>,
+    ) -> Result<Self, FindVersionError> {
+        Ok(Custom {
+            buf,
+            pattern: Source::Regex(re?),
+            group: None,
+            config: Config::default(),
+        })
+    }
+
+    /// Use the named capture group as version, instead of the first one.
+    pub fn with_group(self, group: &'a str) -> Self {
+        Custom {
+            group: Some(group),
+            ..self
+        }
+    }
+
+    pub(crate) fn with_config(self, config: Config<'a>) -> Self {
+        Custom { config, ..self }
     }
 }
 
-#[async_trait::async_trait(?Send)]
-impl<'a, R: AsyncRead + Unpin> VersionFinder for Custom<'a, R> {
-    async fn get_version(&mut self) -> Option<String> {
-        // FIXME: Avoid reading the whole file
-        let mut buffer = Vec::new();
-        self.buf.read_to_end(&mut buffer).await.ok()?;
-
-        let re = Regex::new(self.pattern).unwrap();
-        for line in buffer.into_strings_iter() {
-            if let Some(v) = re.captures(&line).and_then(|c| c.get(1)) {
-                return Some(v.as_str().to_string());
+impl<'a, R: AsyncRead + Unpin> fmt::Debug for Custom<'a, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Custom")
+            .field("pattern", &self.pattern)
+            .field("group", &self.group)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, R> Custom<'a, R>
+where
+    R: AsyncRead + Unpin,
+{
+    /// Get all the capture groups of the first match of the pattern, the
+    /// first one being the whole match, and groups which did not participate
+    /// in the match being `None`.
+    pub(crate) async fn get_captures(
+        &mut self,
+    ) -> Result<Option<Vec<Option<String>>>, FindVersionError> {
+        let re = self.pattern.regex()?;
+        find_map_captures(self.buf, &self.config, &re, |c| {
+            Some(
+                c.iter()
+                    .map(|m| m.map(|m| m.as_str().to_string()))
+                    .collect(),
+            )
+        })
+        .await
+    }
+
+    /// Get the versions of the first `n` matches of the pattern, skipping
+    /// the versions already found unless duplicates are configured to be
+    /// kept. The binary is no longer read once `n` versions are found.
+    pub(crate) async fn get_versions(&mut self, n: usize) -> Result<Vec<String>, FindVersionError> {
+        let mut versions = Vec::new();
+        if n == 0 {
+            return Ok(versions);
+        }
+
+        let re = self.pattern.regex_with_group(self.group)?;
+        let group = self.group;
+        let duplicates = self.config.duplicates;
+        find_map_captures(self.buf, &self.config, &re, |c| {
+            let version = version(c, group)?;
+            if duplicates || !versions.contains(&version) {
+                versions.push(version);
+            }
+            match versions.len() == n {
+                true => Some(()),
+                false => None,
+            }
+        })
+        .await?;
+
+        Ok(versions)
+    }
+}
+
+/// Look for the first match of the pattern for which `f` provides a value,
+/// reading the binary in chunks.
+async fn find_map_captures<R: AsyncRead + Unpin, T>(
+    buf: &mut R,
+    config: &Config<'_>,
+    re: &Regex,
+    mut f: impl FnMut(&Captures<'_>) -> Option<T>,
+) -> Result<Option<T>, FindVersionError> {
+    debug!("looking for pattern {:?}", re.as_str());
+    find_map_strings(buf, config, |line| {
+        let value = re.captures_iter(line).find_map(|c| f(&c))?;
+        debug!("pattern {:?} matched {:?}", re.as_str(), line);
+        Some(value)
+    })
+    .await
+}
+
+/// Read the binary in chunks, as configured, looking at each of its strings
+/// until `f` provides a value for one of them.
+pub(crate) async fn find_map_strings<R: AsyncRead + Unpin, T>(
+    buf: &mut R,
+    config: &Config<'_>,
+    mut f: impl FnMut(&str) -> Option<T>,
+) -> Result<Option<T>, FindVersionError> {
+    let size = config.buffer_size.unwrap_or(CHUNK_SIZE);
+    let mut chunk = vec![0; size];
+    let mut buffer = Vec::with_capacity(2 * size);
+    loop {
+        let n = buf.read(&mut chunk).await?;
+        debug!("read {} bytes looking for strings", n);
+        buffer.extend_from_slice(&chunk[..n]);
+
+        let end = complete_strings_len(&buffer, n == 0, size);
+        let lines = (&buffer[..end])
+            .into_strings_iter()
+            .with_max_length(config.max_string_length);
+        for line in lines {
+            if let Some(value) = f(&line) {
+                return Ok(Some(value));
             }
         }
 
-        None
+        if n == 0 {
+            return Ok(None);
+        }
+        buffer.drain(..end);
+    }
+}
+
+/// Get the length of the start of the buffer made of complete strings, which
+/// can be looked at before reading more of the binary.
+pub(crate) fn complete_strings_len(buffer: &[u8], eof: bool, size: usize) -> usize {
+    if eof {
+        return buffer.len();
+    }
+
+    // A string at the end of the buffer may continue in the next read, so it
+    // is kept for it, unless it is longer than a chunk.
+    let end = buffer
+        .iter()
+        .rposition(|b| !b.is_printable())
+        .map_or(0, |i| i + 1);
+    match buffer.len() - end > size {
+        true => buffer.len(),
+        false => end,
+    }
+}
+
+/// Get the version of a match, which is the given group or the first one.
+fn version(captures: &Captures<'_>, group: Option<&str>) -> Option<String> {
+    match group {
+        Some(group) => captures.name(group),
+        None => captures.get(1),
+    }
+    .map(|v| v.as_str().to_string())
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for Custom<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        let re = self.pattern.regex_with_group(self.group)?;
+        let group = self.group;
+
+        // A match where the group does not participate provides no version,
+        // so the following ones are tried.
+        find_map_captures(self.buf, &self.config, &re, |c| version(c, group)).await
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::version_with_pattern;
-    use tokio::io::AsyncRead;
+    use super::CHUNK_SIZE;
+    use crate::{version, version_with_pattern, BinaryKind, FindVersionError};
+    use tokio::io::{AsyncRead, AsyncSeek};
 
-    async fn fixture(name: &str) -> impl AsyncRead {
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
         use tokio::{fs::File, io::BufReader};
 
         BufReader::new(
@@ -64,9 +328,276 @@ mod test {
         ] {
             assert_eq!(
                 version_with_pattern(&mut fixture(f).await, r"U-Boot(?: SPL)? (\d+.?\.[^\s]+)")
-                    .await,
+                    .await
+                    .unwrap(),
+                Some(v.to_string()),
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn captures() {
+        use crate::version_captures;
+
+        assert_eq!(
+            version_captures(
+                &mut fixture("arm-spl").await,
+                r"(U-Boot)( SPL)? (\d+.?\.[^\s]+) \((\w+)"
+            )
+            .await
+            .unwrap(),
+            Some(vec![
+                Some("U-Boot 2017.11+fslc+ga07698f (Sep".to_string()),
+                Some("U-Boot".to_string()),
+                None,
+                Some("2017.11+fslc+ga07698f".to_string()),
+                Some("Sep".to_string()),
+            ]),
+        );
+        assert_eq!(
+            version_captures(&mut fixture("arm-spl").await, r"(Barebox) (\S+)")
+                .await
+                .unwrap(),
+            None,
+        );
+    }
+
+    #[tokio::test]
+    async fn from_bytes() {
+        use crate::version_with_pattern_from_bytes;
+
+        assert_eq!(
+            version_with_pattern_from_bytes(
+                include_bytes!("../tests/fixtures/uboot/arm-spl"),
+                r"U-Boot(?: SPL)? (\d+.?\.[^\s]+)"
+            )
+            .await
+            .unwrap(),
+            Some("2017.11+fslc+ga07698f".to_string()),
+        );
+    }
+
+    #[tokio::test]
+    async fn from_path() {
+        use crate::version_with_pattern_from_path;
+
+        assert_eq!(
+            version_with_pattern_from_path(
+                "tests/fixtures/uboot/arm-u-boot-dtb.img",
+                r"U-Boot(?: SPL)? (\d+.?\.[^\s]+)"
+            )
+            .await
+            .unwrap(),
+            Some("2019.04-00014-gc93ced78db".to_string()),
+        );
+    }
+
+    #[tokio::test]
+    async fn spanning_chunks() {
+        let mut data = vec![0; CHUNK_SIZE - 8];
+        data.extend_from_slice(b"U-Boot 2023.10-rc3 (Oct 01 2023 - 10:00:00 +0000)\0");
+
+        assert_eq!(
+            version_with_pattern(
+                &mut std::io::Cursor::new(data),
+                r"U-Boot(?: SPL)? (\d+.?\.[^\s]+)"
+            )
+            .await
+            .unwrap(),
+            Some("2023.10-rc3".to_string()),
+        );
+    }
+
+    #[tokio::test]
+    async fn all() {
+        use crate::version_all_with_pattern;
+
+        let pattern = r"U-Boot(?: SPL)? (\d+.?\.[^\s]+)";
+        assert_eq!(
+            version_all_with_pattern(&mut fixture("arm-spl").await, pattern)
+                .await
+                .unwrap(),
+            vec!["2017.11+fslc+ga07698f".to_string()],
+        );
+        for data in [vec![], vec![0; 0x1000]] {
+            assert_eq!(
+                version_all_with_pattern(&mut std::io::Cursor::new(data), pattern)
+                    .await
+                    .unwrap(),
+                Vec::<String>::new(),
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn compiled_pattern() {
+        use crate::version_with_compiled_pattern;
+        use regex::Regex;
+
+        let re = Regex::new(r"U-Boot(?: SPL)? (\d+.?\.[^\s]+)").unwrap();
+        for (f, v) in &[
+            ("arm-spl", "2017.11+fslc+ga07698f"),
+            ("arm-u-boot-dtb.img", "2019.04-00014-gc93ced78db"),
+        ] {
+            assert_eq!(
+                version_with_compiled_pattern(&mut fixture(f).await, &re)
+                    .await
+                    .unwrap(),
                 Some(v.to_string()),
             );
         }
     }
+
+    #[tokio::test]
+    async fn stream() {
+        use crate::iter_versions;
+        use futures_util::{pin_mut, StreamExt};
+
+        let data = b"v1.0\0v2.0\0v1.0\0v3.0\0".to_vec();
+        let mut cursor = std::io::Cursor::new(data);
+        {
+            let versions = iter_versions(&mut cursor, BinaryKind::custom("v", r"v(\d+\.\d+)"));
+            pin_mut!(versions);
+            for v in ["1.0", "2.0", "3.0"] {
+                assert_eq!(versions.next().await.unwrap().unwrap(), v);
+            }
+            assert!(versions.next().await.is_none());
+        }
+
+        // Dropping the stream early releases the reader
+        cursor.set_position(0);
+        {
+            let versions = iter_versions(&mut cursor, BinaryKind::custom("v", r"v(\d+\.\d+)"));
+            pin_mut!(versions);
+            assert_eq!(versions.next().await.unwrap().unwrap(), "1.0");
+        }
+        assert_eq!(cursor.position(), 20);
+    }
+
+    #[tokio::test]
+    async fn stream_incremental() {
+        use crate::iter_versions;
+        use futures_util::{pin_mut, StreamExt};
+
+        let mut data = b"v1.0\0".to_vec();
+        data.resize(4 * CHUNK_SIZE, 0);
+        data.extend_from_slice(b"v2.0\0");
+        let mut cursor = std::io::Cursor::new(data);
+        {
+            let versions = iter_versions(&mut cursor, BinaryKind::custom("v", r"v(\d+\.\d+)"));
+            pin_mut!(versions);
+            assert_eq!(versions.next().await.unwrap().unwrap(), "1.0");
+        }
+        // The version is yielded as soon as its chunk is read
+        assert_eq!(cursor.position(), CHUNK_SIZE as u64);
+
+        cursor.set_position(0);
+        let versions = iter_versions(&mut cursor, BinaryKind::custom("v", r"v(\d+\.\d+)"));
+        pin_mut!(versions);
+        assert_eq!(versions.next().await.unwrap().unwrap(), "1.0");
+        assert_eq!(versions.next().await.unwrap().unwrap(), "2.0");
+        assert!(versions.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn invalid_pattern() {
+        assert!(matches!(
+            version_with_pattern(&mut fixture("arm-spl").await, r"U-Boot (\d+").await,
+            Err(FindVersionError::InvalidPattern(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn custom_kind() {
+        for (f, v) in &[
+            ("arm-spl", "2017.11+fslc+ga07698f"),
+            ("arm-u-boot-dtb.img", "2019.04-00014-gc93ced78db"),
+        ] {
+            assert_eq!(
+                version(
+                    &mut fixture(f).await,
+                    BinaryKind::custom("u-boot", r"U-Boot(?: SPL)? (\d+.?\.[^\s]+)")
+                )
+                .await
+                .unwrap(),
+                Some(v.to_string()),
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn named_group() {
+        use crate::version_with_named_group;
+        use std::io::Cursor;
+
+        for (pattern, group, v) in [
+            (
+                r"U-Boot(?: SPL)? (?P<ver>\d+.?\.[^\s]+)",
+                "ver",
+                Some("2017.11+fslc+ga07698f"),
+            ),
+            (r"(U-Boot) (?P<ver>\d+)\.", "ver", Some("2017")),
+            (r"U-Boot(?: SPL)? (?P<ver>\d{5})?", "ver", None),
+        ] {
+            assert_eq!(
+                version_with_named_group(&mut fixture("arm-spl").await, pattern, group)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+
+        assert_eq!(
+            version_with_named_group(
+                &mut Cursor::new(b"version=1.2\0version=1.2.3\0"),
+                r"version=(?P<ver>\d+\.\d+\.\d+)?",
+                "ver"
+            )
+            .await
+            .unwrap(),
+            Some("1.2.3".to_string()),
+        );
+
+        assert!(matches!(
+            version_with_named_group(&mut fixture("arm-spl").await, r"U-Boot (\d+)", "ver").await,
+            Err(FindVersionError::InvalidPattern(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn first_n() {
+        use crate::version_first_n;
+
+        let data = b"\0lib 1.0.0\0lib 1.0.0 app 2.1.0\0lib 1.0.0\0lib 3.0.0\0lib 4.0.0\0";
+        for (n, versions) in [
+            (0, &[][..]),
+            (2, &["1.0.0", "2.1.0"][..]),
+            (3, &["1.0.0", "2.1.0", "3.0.0"][..]),
+            (10, &["1.0.0", "2.1.0", "3.0.0", "4.0.0"][..]),
+        ] {
+            assert_eq!(
+                version_first_n(&mut &data[..], r"\w+ (\d+\.\d+\.\d+)", n)
+                    .await
+                    .unwrap(),
+                versions,
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn try_from_reader() {
+        use crate::{Custom, VersionFinder};
+        use regex::Regex;
+
+        let mut binary = &b"\0vendor firmware v4.2\0"[..];
+        let mut finder =
+            Custom::try_from_reader(&mut binary, Regex::new(r"firmware v(\S+)")).unwrap();
+        assert_eq!(finder.get_version().await.unwrap(), Some("4.2".to_string()));
+
+        let invalid = r"firmware v(\S+";
+        assert!(matches!(
+            Custom::try_from_reader(&mut binary, Regex::new(invalid)),
+            Err(FindVersionError::InvalidPattern(_))
+        ));
+    }
 }
@@ -0,0 +1,223 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    android::BOOT_MAGIC,
+    androidota::PAYLOAD_MAGIC,
+    atf::FIP_TOC_HEADER_NAME,
+    efi_capsule::EFI_CAPSULE_GUID,
+    elf_utils::XZ_MAGIC,
+    fit::FDT_MAGIC,
+    linuxkernel::{AARCH64_IMAGE_MAGIC_NUMBER, ARM_ZIMAGE_MAGIC_NUMBER, RISCV_IMAGE_MAGIC_NUMBER},
+    python_wheel::ZIP_MAGIC,
+    uboot_script::IH_TYPE_SCRIPT,
+    uefi::FMP_CAPSULE_GUID,
+    uimage::{UIMAGE_HEADER_SIZE, UIMAGE_MAGIC_NUMBER},
+    BinaryKind,
+};
+use std::io::{self, SeekFrom};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+// Enough to hold the x86 boot flag, the last header field looked at
+const HEADER_SIZE: usize = 0x200;
+
+// U-Boot Image type used by kernels (IH_TYPE_KERNEL)
+const IH_TYPE_KERNEL: u8 = 2;
+
+// ARM Barebox images Magic word, stored at offset 0x0020 of file
+const BAREBOX_ARM_MAGIC: &[u8] = b"barebox";
+
+// ar archive Magic Number, used by OpenWrt packages built by dpkg
+const AR_MAGIC: &[u8] = b"!<arch>\n";
+
+fn u32_be_at(header: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        header.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn u32_le_at(header: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        header.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn is_hex_record(header: &[u8], start: &[u8]) -> bool {
+    header.starts_with(start)
+        && header.len() > start.len() + 2
+        && header[start.len()..start.len() + 2]
+            .iter()
+            .all(u8::is_ascii_hexdigit)
+}
+
+fn detect(header: &[u8]) -> Option<BinaryKind> {
+    if u32_be_at(header, 0x0000) == Some(UIMAGE_MAGIC_NUMBER) && header.len() >= UIMAGE_HEADER_SIZE
+    {
+        // The image type (ih_type) tells the scripts from the kernels
+        return Some(match header[0x001E] {
+            IH_TYPE_SCRIPT => BinaryKind::UBootScript,
+            IH_TYPE_KERNEL => BinaryKind::LinuxKernel,
+            _ => BinaryKind::UImageHeader,
+        });
+    }
+
+    if header.starts_with(PAYLOAD_MAGIC) {
+        return Some(BinaryKind::AndroidOtaPayload);
+    }
+
+    if u32_le_at(header, 0x0000) == Some(FIP_TOC_HEADER_NAME) {
+        return Some(BinaryKind::ArmTrustedFirmware);
+    }
+
+    if header.starts_with(BOOT_MAGIC) {
+        return Some(BinaryKind::AndroidBoot);
+    }
+
+    if header.starts_with(&EFI_CAPSULE_GUID) {
+        return Some(BinaryKind::EfiCapsule);
+    }
+
+    if header.starts_with(&FMP_CAPSULE_GUID) {
+        return Some(BinaryKind::UEFICapsule);
+    }
+
+    if u32_le_at(header, 0x0024) == Some(ARM_ZIMAGE_MAGIC_NUMBER)
+        || u32_le_at(header, 0x0038) == Some(AARCH64_IMAGE_MAGIC_NUMBER)
+        || u32_le_at(header, 0x0038) == Some(RISCV_IMAGE_MAGIC_NUMBER)
+        || header.get(0x01FE..0x0200) == Some(&[0x55, 0xAA])
+    {
+        return Some(BinaryKind::LinuxKernel);
+    }
+
+    if header.get(0x0020..0x0020 + BAREBOX_ARM_MAGIC.len()) == Some(BAREBOX_ARM_MAGIC) {
+        return Some(BinaryKind::Barebox);
+    }
+
+    if u32_be_at(header, 0x0000) == Some(FDT_MAGIC) || u32_le_at(header, 0x0000) == Some(FDT_MAGIC)
+    {
+        return Some(BinaryKind::FIT);
+    }
+
+    if header.starts_with(XZ_MAGIC) {
+        return Some(BinaryKind::XzPayload);
+    }
+
+    if header.starts_with(ZIP_MAGIC) {
+        return Some(BinaryKind::PythonWheel);
+    }
+
+    if header.starts_with(AR_MAGIC) {
+        return Some(BinaryKind::OpenwrtPackage);
+    }
+
+    // Intel HEX or Motorola S-record (starting with its S0 header record)
+    if is_hex_record(header, b":") || is_hex_record(header, b"S0") {
+        return Some(BinaryKind::MicrocontrollerFirmware);
+    }
+
+    None
+}
+
+/// Detect the binary kind from the file header, leaving the reader at the
+/// begin of the file.
+pub(crate) async fn detect_kind<R: AsyncRead + AsyncSeek + Unpin>(
+    buf: &mut R,
+) -> io::Result<Option<BinaryKind>> {
+    buf.seek(SeekFrom::Start(0)).await?;
+    let mut header = Vec::with_capacity(HEADER_SIZE);
+    (&mut *buf)
+        .take(HEADER_SIZE as u64)
+        .read_to_end(&mut header)
+        .await?;
+    buf.seek(SeekFrom::Start(0)).await?;
+
+    Ok(detect(&header))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{detect_kind, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek, AsyncSeekExt};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, k) in &[
+            ("linuxkernel/arm-uImage", Some(BinaryKind::LinuxKernel)),
+            ("linuxkernel/arm-zImage", Some(BinaryKind::LinuxKernel)),
+            ("linuxkernel/arm64-Image", Some(BinaryKind::LinuxKernel)),
+            ("linuxkernel/riscv64-Image", Some(BinaryKind::LinuxKernel)),
+            ("linuxkernel/x86-bzImage", Some(BinaryKind::LinuxKernel)),
+            ("uboot/arm-u-boot-dtb.img", Some(BinaryKind::UImageHeader)),
+            ("ubootscript/boot.scr", Some(BinaryKind::UBootScript)),
+            ("android/payload.bin", Some(BinaryKind::AndroidOtaPayload)),
+            ("android/boot-v2.img", Some(BinaryKind::AndroidBoot)),
+            ("atf/fip.bin", Some(BinaryKind::ArmTrustedFirmware)),
+            ("efi/capsule.bin", Some(BinaryKind::EfiCapsule)),
+            ("efi/fmp-capsule.bin", Some(BinaryKind::UEFICapsule)),
+            ("barebox/arm-barebox.img", Some(BinaryKind::Barebox)),
+            ("fit/kernel.itb", Some(BinaryKind::FIT)),
+            ("xzpayload/vmlinux.xz", Some(BinaryKind::XzPayload)),
+            (
+                "wheel/requests-2.26.0-py2.py3-none-any.whl",
+                Some(BinaryKind::PythonWheel),
+            ),
+            ("openwrt/uhttpd_ar.ipk", Some(BinaryKind::OpenwrtPackage)),
+            (
+                "mcu/firmware.hex",
+                Some(BinaryKind::MicrocontrollerFirmware),
+            ),
+            (
+                "mcu/firmware.srec",
+                Some(BinaryKind::MicrocontrollerFirmware),
+            ),
+            ("uboot/arm-spl", None),
+        ] {
+            let mut file = fixture(f).await;
+            let kind = detect_kind(&mut file).await.unwrap();
+            assert_eq!(
+                format!("{:?}", kind),
+                format!("{:?}", k),
+                "{} kind mismatch",
+                f
+            );
+            assert_eq!(file.stream_position().await.unwrap(), 0);
+        }
+
+        for (image, k) in [
+            (
+                crate::helpers::make_barebox_image("2023.01.0"),
+                BinaryKind::Barebox,
+            ),
+            (
+                crate::helpers::make_linux_bzimage("6.1.0"),
+                BinaryKind::LinuxKernel,
+            ),
+            (
+                crate::helpers::make_linux_uimage("6.1.0"),
+                BinaryKind::LinuxKernel,
+            ),
+        ] {
+            let kind = detect_kind(&mut std::io::Cursor::new(image)).await.unwrap();
+            assert_eq!(format!("{:?}", kind), format!("{:?}", Some(k)));
+        }
+    }
+
+    #[tokio::test]
+    async fn empty() {
+        assert!(detect_kind(&mut std::io::Cursor::new(&[]))
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
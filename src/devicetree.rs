@@ -0,0 +1,91 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    fit::{cstr, read_fdt, walk},
+    FindVersionError, VersionFinder,
+};
+use std::str;
+use tokio::io::{AsyncRead, AsyncSeek};
+
+/// Find the version among the root node properties: the `linux,version`
+/// value if any, or the most specific entry of the `compatible` list.
+fn find_version(data: &[u8]) -> Option<String> {
+    let mut compatible = None;
+    let mut linux_version = None;
+    walk(data, |path, property| {
+        // The root node is the only one at depth 1, and its properties come
+        // before its child nodes
+        if path.len() != 1 {
+            return None;
+        }
+
+        match property {
+            Some((b"compatible", value)) => compatible = cstr(value),
+            Some((b"linux,version", value)) => linux_version = cstr(value),
+            _ => {}
+        }
+        Some(())
+    });
+
+    linux_version
+        .or(compatible)
+        .filter(|v| !v.is_empty())
+        .and_then(|v| str::from_utf8(v).ok())
+        .map(str::to_string)
+}
+
+pub(crate) struct DeviceTree<'a, R: AsyncRead + AsyncSeek + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> DeviceTree<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        DeviceTree { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> VersionFinder for DeviceTree<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        Ok(read_fdt(self.buf)
+            .await?
+            .and_then(|data| find_version(&data)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("dtb/imx6q-sabresd.dtb", Some("fsl,imx6q-sabresd")),
+            ("dtb/imx8mm-evk-v16.dtb", Some("5.15.71-2.2.0")),
+            ("fit/kernel-le.itb", None),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::DeviceTree)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+                "{} version mismatch",
+                f
+            );
+        }
+    }
+}
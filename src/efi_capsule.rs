@@ -0,0 +1,122 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{strings::IntoStringsIter, FindVersionError, VersionFinder};
+use regex::Regex;
+use std::io::{self, SeekFrom};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+// EFI_CAPSULE_GUID {3B6686BD-0D76-4030-B70E-B5519E2FC5A0}, as stored in the
+// file (the first three fields are little-endian)
+pub(crate) const EFI_CAPSULE_GUID: [u8; 16] = [
+    0xbd, 0x86, 0x66, 0x3b, 0x76, 0x0d, 0x30, 0x40, 0xb7, 0x0e, 0xb5, 0x51, 0x9e, 0x2f, 0xc5, 0xa0,
+];
+
+/// The fields of EFI_CAPSULE_HEADER needed to locate the capsule payload.
+pub(crate) struct CapsuleHeader {
+    pub(crate) header_size: u32,
+    pub(crate) image_size: u32,
+}
+
+/// Read the capsule header, verifying it has the expected capsule GUID.
+pub(crate) async fn read_capsule_header<R: AsyncRead + AsyncSeek + Unpin>(
+    buf: &mut R,
+    guid: &[u8; 16],
+) -> io::Result<Option<CapsuleHeader>> {
+    // Taken from: UEFI Specification, EFI_CAPSULE_HEADER
+    //
+    // Offset  Size  Name              Meaning
+    // 0000    16    CapsuleGuid       GUID that defines the capsule contents
+    // 0010    4     HeaderSize        Size of the capsule header
+    // 0014    4     Flags             Capsule flags
+    // 0018    4     CapsuleImageSize  Size of the whole capsule, including the header
+    buf.seek(SeekFrom::Start(0)).await?;
+    let mut capsule_guid = [0; 16];
+    buf.read_exact(&mut capsule_guid).await?;
+    if &capsule_guid != guid {
+        return Ok(None);
+    }
+
+    let header_size = buf.read_u32_le().await?;
+    let _flags = buf.read_u32_le().await?;
+    let image_size = buf.read_u32_le().await?;
+    if image_size < header_size {
+        return Ok(None);
+    }
+
+    Ok(Some(CapsuleHeader {
+        header_size,
+        image_size,
+    }))
+}
+
+pub(crate) struct EfiCapsule<'a, R: AsyncRead + AsyncSeek + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> EfiCapsule<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        EfiCapsule { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> VersionFinder for EfiCapsule<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        let header = match read_capsule_header(self.buf, &EFI_CAPSULE_GUID).await? {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+
+        // Read the capsule payload, which follows the header
+        self.buf
+            .seek(SeekFrom::Start(header.header_size.into()))
+            .await?;
+        let mut payload = Vec::new();
+        (&mut *self.buf)
+            .take((header.image_size - header.header_size).into())
+            .read_to_end(&mut payload)
+            .await?;
+
+        let re = Regex::new(r"[Vv]ersion[: ]+(?P<version>[0-9]+\.[0-9]+[^\s]*)").unwrap();
+        for line in payload.into_strings_iter() {
+            if let Some(v) = re.captures(&line).and_then(|c| c.name("version")) {
+                return Ok(Some(v.as_str().to_string()));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("efi/capsule.bin", Some("1.2.3-beta")),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::EfiCapsule)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+}
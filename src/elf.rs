@@ -0,0 +1,119 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    elf_utils::{parse_elf_sections, read_elf_image, ELF_MAGIC},
+    FindVersionError, VersionFinder,
+};
+use regex::Regex;
+use std::str;
+use tokio::io::AsyncRead;
+
+// Section holding the toolchain identification strings
+const COMMENT_SECTION: &str = ".comment";
+
+pub(crate) struct ElfComment<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + Unpin> ElfComment<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        ElfComment { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for ElfComment<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        // Binaries may be shipped XZ compressed (e.g: GKI modules)
+        let data = match read_elf_image(self.buf).await? {
+            Some(data) if data.starts_with(ELF_MAGIC) => data,
+            _ => return Ok(None),
+        };
+
+        let comment = parse_elf_sections(&data)
+            .into_iter()
+            .find(|s| s.name == COMMENT_SECTION)
+            .and_then(|s| {
+                let start = usize::try_from(s.offset).ok()?;
+                let end = usize::try_from(s.offset.checked_add(s.size)?).ok()?;
+                data.get(start..end)
+            });
+        let comment = match comment {
+            Some(comment) => comment,
+            None => return Ok(None),
+        };
+
+        // The version is a standalone word, so the distribution package
+        // version inside of the parenthesis is skipped (e.g: "GCC: (Debian
+        // 12.2.0-14) 12.2.0").
+        let re = Regex::new(r"(?:^|\s)(?P<version>\d+\.\d+[^\s()]*)(?:\s|$)").unwrap();
+        Ok(comment
+            .split(|&b| b == 0)
+            .filter_map(|s| str::from_utf8(s).ok())
+            .find_map(|s| re.captures(s).and_then(|c| c.name("version")))
+            .map(|v| v.as_str().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("elf/x86_64-comment.o", Some("12.2.0")),
+            ("elf/x86_64-comment.o.xz", Some("12.2.0")),
+            ("elf/x86_64-sections.o", None),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::ElfComment)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn toolchains() {
+        use std::io::Cursor;
+
+        let data = std::fs::read("tests/fixtures/elf/x86_64-comment.o").unwrap();
+        let original = b"GCC: (Debian 12.2.0-14+deb12u1) 12.2.0\0";
+        let offset = data
+            .windows(original.len())
+            .position(|w| w == original)
+            .unwrap();
+
+        for (comment, v) in [
+            (&b"GCC: (GNU) 12.3.0\0"[..], Some("12.3.0")),
+            (&b"\0Linker: LLD 15.0.7\0"[..], Some("15.0.7")),
+            (&b"GCC: (GNU)\0"[..], None),
+        ] {
+            let mut data = data.clone();
+            data[offset..offset + original.len()].fill(0);
+            data[offset..offset + comment.len()].copy_from_slice(comment);
+            assert_eq!(
+                version(&mut Cursor::new(data), BinaryKind::ElfComment)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+}
@@ -0,0 +1,393 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use std::{
+    io::{self, SeekFrom},
+    str,
+};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+// ELF Magic Number
+pub(crate) const ELF_MAGIC: &[u8] = b"\x7fELF";
+
+// XZ Magic Number, used by compressed kernel modules (e.g: .ko.xz)
+pub(crate) const XZ_MAGIC: &[u8] = b"\xfd7zXZ\x00";
+
+// Section type for sections which occupy no space in the file (e.g: .bss)
+const SHT_NOBITS: u32 = 8;
+
+/// A section found in the ELF section header table.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ElfSection {
+    pub(crate) name: String,
+    pub(crate) offset: u64,
+    pub(crate) size: u64,
+}
+
+/// Helper to read the ELF fields honoring the file class and endianness.
+struct Elf<'a> {
+    buf: &'a [u8],
+    is_64: bool,
+    is_le: bool,
+}
+
+impl<'a> Elf<'a> {
+    fn from_bytes(buf: &'a [u8]) -> Option<Self> {
+        if !buf.starts_with(ELF_MAGIC) {
+            return None;
+        }
+
+        // EI_CLASS is 1 for 32-bit and 2 for 64-bit objects while EI_DATA is
+        // 1 for little-endian and 2 for big-endian ones.
+        let is_64 = match buf.get(4)? {
+            1 => false,
+            2 => true,
+            _ => return None,
+        };
+        let is_le = match buf.get(5)? {
+            1 => true,
+            2 => false,
+            _ => return None,
+        };
+
+        Some(Elf { buf, is_64, is_le })
+    }
+
+    fn bytes<const N: usize>(&self, offset: u64) -> Option<[u8; N]> {
+        let start = usize::try_from(offset).ok()?;
+        self.buf.get(start..start.checked_add(N)?)?.try_into().ok()
+    }
+
+    fn u16(&self, offset: u64) -> Option<u16> {
+        let b = self.bytes(offset)?;
+        Some(if self.is_le {
+            u16::from_le_bytes(b)
+        } else {
+            u16::from_be_bytes(b)
+        })
+    }
+
+    fn u32(&self, offset: u64) -> Option<u32> {
+        let b = self.bytes(offset)?;
+        Some(if self.is_le {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        })
+    }
+
+    fn u64(&self, offset: u64) -> Option<u64> {
+        let b = self.bytes(offset)?;
+        Some(if self.is_le {
+            u64::from_le_bytes(b)
+        } else {
+            u64::from_be_bytes(b)
+        })
+    }
+
+    // Read an address sized field, which depends on the file class.
+    fn addr(&self, offset: u64) -> Option<u64> {
+        if self.is_64 {
+            self.u64(offset)
+        } else {
+            self.u32(offset).map(u64::from)
+        }
+    }
+
+    /// Read the location of the section header table, as the offset, entry
+    /// size and number of entries, along with the index of the entry
+    /// containing the section names.
+    fn section_table(&self) -> Option<(u64, u16, u16, u16)> {
+        // Offset  Size   Name          Meaning
+        // 32/64   32/64
+        // ...
+        // 20/28   4/8    e_shoff       Start of the section header table
+        // 2E/3A   2      e_shentsize   Size of a section header table entry
+        // 30/3C   2      e_shnum       Number of entries in the section header table
+        // 32/3E   2      e_shstrndx    Index of the entry containing section names
+        if self.is_64 {
+            Some((
+                self.u64(0x28)?,
+                self.u16(0x3A)?,
+                self.u16(0x3C)?,
+                self.u16(0x3E)?,
+            ))
+        } else {
+            Some((
+                self.addr(0x20)?,
+                self.u16(0x2E)?,
+                self.u16(0x30)?,
+                self.u16(0x32)?,
+            ))
+        }
+    }
+
+    /// Read the section header table entries, starting at `shoff`, as their
+    /// name offset, type, file offset and size.
+    fn section_headers(
+        &self,
+        shoff: u64,
+        shentsize: u16,
+        shnum: u16,
+    ) -> Option<Vec<(u32, u32, u64, u64)>> {
+        // Section header entry layout:
+        //   sh_name (4), sh_type (4), sh_flags (4/8), sh_addr (4/8),
+        //   sh_offset (4/8), sh_size (4/8), ...
+        let (offset_field, size_field) = if self.is_64 {
+            (0x18, 0x20)
+        } else {
+            (0x10, 0x14)
+        };
+        (0..u64::from(shnum))
+            .map(|i| {
+                let entry = shoff + i * u64::from(shentsize);
+                Some((
+                    self.u32(entry)?,
+                    self.u32(entry + 0x04)?,
+                    self.addr(entry + offset_field)?,
+                    self.addr(entry + size_field)?,
+                ))
+            })
+            .collect()
+    }
+
+    fn sections(&self) -> Option<Vec<ElfSection>> {
+        let (shoff, shentsize, shnum, shstrndx) = self.section_table()?;
+        let headers = self.section_headers(shoff, shentsize, shnum)?;
+
+        let (_, _, strtab_offset, strtab_size) = *headers.get(usize::from(shstrndx))?;
+        let strtab = self.buf.get(
+            usize::try_from(strtab_offset).ok()?
+                ..usize::try_from(strtab_offset.checked_add(strtab_size)?).ok()?,
+        )?;
+
+        Some(named_sections(headers, strtab))
+    }
+}
+
+/// Name the sections from their headers, skipping those which do not occupy
+/// space in the file.
+fn named_sections(headers: Vec<(u32, u32, u64, u64)>, strtab: &[u8]) -> Vec<ElfSection> {
+    headers
+        .into_iter()
+        .filter(|(_, kind, ..)| *kind != SHT_NOBITS)
+        .filter_map(|(name, _, offset, size)| {
+            let name = strtab.get(usize::try_from(name).ok()?..)?;
+            let name = &name[..name.iter().position(|&b| b == 0)?];
+            Some(ElfSection {
+                name: str::from_utf8(name).ok()?.to_string(),
+                offset,
+                size,
+            })
+        })
+        .collect()
+}
+
+/// Read up to `size` bytes of the binary from the given offset.
+pub(crate) async fn read_at<R: AsyncRead + AsyncSeek + Unpin>(
+    buf: &mut R,
+    offset: u64,
+    size: u64,
+) -> io::Result<Vec<u8>> {
+    buf.seek(SeekFrom::Start(offset)).await?;
+    let mut data = Vec::new();
+    buf.take(size).read_to_end(&mut data).await?;
+    Ok(data)
+}
+
+/// Read the ELF section header table from the binary, only reading the ELF
+/// header, the section header table and the section names.
+///
+/// An empty list is returned for non-ELF or malformed binaries, as done by
+/// [`parse_elf_sections`].
+pub(crate) async fn read_elf_sections<R: AsyncRead + AsyncSeek + Unpin>(
+    buf: &mut R,
+) -> io::Result<Vec<ElfSection>> {
+    // The 64-bit ELF header is the largest one
+    let header = read_at(buf, 0, 0x40).await?;
+    let elf = match Elf::from_bytes(&header) {
+        Some(elf) => elf,
+        None => return Ok(Vec::new()),
+    };
+    let (shoff, shentsize, shnum, shstrndx) = match elf.section_table() {
+        Some(table) => table,
+        None => return Ok(Vec::new()),
+    };
+    debug!(
+        "read ELF section header table of {} entries at offset {:#x}",
+        shnum, shoff
+    );
+
+    let table = read_at(buf, shoff, u64::from(shentsize) * u64::from(shnum)).await?;
+    let table = Elf {
+        buf: &table,
+        is_64: elf.is_64,
+        is_le: elf.is_le,
+    };
+    let headers = match table.section_headers(0, shentsize, shnum) {
+        Some(headers) => headers,
+        None => return Ok(Vec::new()),
+    };
+    let (_, _, strtab_offset, strtab_size) = match headers.get(usize::from(shstrndx)) {
+        Some(strtab) => *strtab,
+        None => return Ok(Vec::new()),
+    };
+
+    let strtab = read_at(buf, strtab_offset, strtab_size).await?;
+    Ok(named_sections(headers, &strtab))
+}
+
+/// Parse the ELF section header table from the whole binary contents.
+///
+/// Sections which do not occupy space in the file (e.g: .bss) are skipped and
+/// an empty list is returned for non-ELF or malformed binaries.
+pub(crate) fn parse_elf_sections(buf: &[u8]) -> Vec<ElfSection> {
+    Elf::from_bytes(buf)
+        .and_then(|elf| elf.sections())
+        .unwrap_or_default()
+}
+
+/// Read the whole ELF binary contents, transparently decompressing it when it
+/// is XZ compressed.
+///
+/// `None` is returned when the XZ compressed contents cannot be decompressed.
+pub(crate) async fn read_elf_image<R: AsyncRead + Unpin>(
+    buf: &mut R,
+) -> io::Result<Option<Vec<u8>>> {
+    let mut data = Vec::new();
+    buf.read_to_end(&mut data).await?;
+
+    if data.starts_with(XZ_MAGIC) {
+        let mut uncompressed = Vec::new();
+        return Ok(
+            compress_tools::tokio_support::uncompress_data(&data[..], &mut uncompressed)
+                .await
+                .ok()
+                .map(|_| uncompressed),
+        );
+    }
+
+    Ok(Some(data))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sections() {
+        let buf = std::fs::read("tests/fixtures/elf/x86_64-sections.o").unwrap();
+        let sections = parse_elf_sections(&buf);
+
+        assert_eq!(
+            sections.iter().find(|s| s.name == ".rodata"),
+            Some(&ElfSection {
+                name: ".rodata".to_string(),
+                offset: 0xc0,
+                size: 0x32
+            })
+        );
+        assert!(sections.iter().all(|s| s.name != ".bss"));
+    }
+
+    #[test]
+    fn not_elf() {
+        assert!(parse_elf_sections(b"\0\0\0\0\0\0\0\0").is_empty());
+        assert!(parse_elf_sections(b"\x7fELF").is_empty());
+    }
+
+    #[tokio::test]
+    async fn version_in_sections() {
+        use crate::{version_in_sections, BinaryKind};
+        use tokio::{fs::File, io::BufReader};
+
+        for (f, sections, v) in &[
+            ("x86_64-sections.o", &[".rodata"][..], Some("2021.01-rc5")),
+            ("x86_64-sections.o", &[".data"][..], Some("2020.10")),
+            (
+                "x86_64-sections.o",
+                &[".text", ".rodata", ".data"][..],
+                Some("2021.01-rc5"),
+            ),
+            ("x86_64-sections.o", &[".text"][..], None),
+            ("x86_64-sections.o", &[".missing"][..], None),
+            (
+                "x86_64-sections.o.xz",
+                &[".rodata"][..],
+                Some("2021.01-rc5"),
+            ),
+        ] {
+            let mut f = BufReader::new(
+                File::open(&format!("tests/fixtures/elf/{}", f))
+                    .await
+                    .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", f)),
+            );
+            assert_eq!(
+                version_in_sections(&mut f, BinaryKind::UBoot, sections)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn version_in_malformed_section() {
+        use crate::{version_in_sections, BinaryKind};
+
+        // Move the .rodata section (at 0xc0) to the end of the address space,
+        // so its end overflows, and past the end of the binary
+        let data = std::fs::read("tests/fixtures/elf/x86_64-sections.o").unwrap();
+        let shoff = u64::from_le_bytes(data[0x28..0x30].try_into().unwrap()) as usize;
+        let shentsize = usize::from(u16::from_le_bytes([data[0x3A], data[0x3B]]));
+        let entry = (shoff..data.len())
+            .step_by(shentsize)
+            .find(|&e| data[e + 0x18..e + 0x20] == 0xc0u64.to_le_bytes())
+            .unwrap();
+
+        for offset in [u64::MAX - 0x10, 0x10_0000] {
+            let mut data = data.clone();
+            data[entry + 0x18..entry + 0x20].copy_from_slice(&offset.to_le_bytes());
+            assert_eq!(
+                version_in_sections(
+                    &mut io::Cursor::new(data),
+                    BinaryKind::UBoot,
+                    &[".rodata", ".data"]
+                )
+                .await
+                .unwrap(),
+                Some("2020.10".to_string()),
+                "{:#x}",
+                offset
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn version_in_elf_section() {
+        use crate::version_in_elf_section;
+        use tokio::{fs::File, io::BufReader};
+
+        for (f, section, v) in &[
+            ("elf/x86_64-sections.o", ".rodata", Some("2021.01-rc5")),
+            ("elf/x86_64-sections.o", ".data", Some("2020.10")),
+            ("elf/x86_64-sections.o", ".text", None),
+            ("elf/x86_64-sections.o", ".missing", None),
+            ("elf/x86_64-sections.o.xz", ".rodata", None),
+            ("uboot/arm-spl", ".rodata", None),
+        ] {
+            let mut f = BufReader::new(
+                File::open(&format!("tests/fixtures/{}", f))
+                    .await
+                    .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", f)),
+            );
+            assert_eq!(
+                version_in_elf_section(&mut f, section, r"U-Boot (\S+)")
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+}
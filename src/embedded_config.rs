@@ -0,0 +1,91 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{elf_utils::XZ_MAGIC, strings::IntoStringsIter, FindVersionError, VersionFinder};
+use regex::Regex;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+// Gzip Magic Number, used by compressed initramfs images (e.g: .cpio.gz)
+const GZIP_MAGIC: &[u8] = b"\x1f\x8b";
+
+pub(crate) struct EmbeddedConfig<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + Unpin> EmbeddedConfig<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        EmbeddedConfig { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for EmbeddedConfig<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        // FIXME: Avoid reading the whole file
+        let mut buffer = Vec::new();
+        self.buf.read_to_end(&mut buffer).await?;
+
+        // Images compressed as a whole (e.g: initramfs) are transparently
+        // decompressed, so the os-release contents can be found.
+        if buffer.starts_with(GZIP_MAGIC) || buffer.starts_with(XZ_MAGIC) {
+            let mut uncompressed = Vec::new();
+            if compress_tools::tokio_support::uncompress_data(&buffer[..], &mut uncompressed)
+                .await
+                .is_err()
+            {
+                return Ok(None);
+            }
+            buffer = uncompressed;
+        }
+
+        // The quoted form is tried first, so the quotes are not taken as part
+        // of the version.
+        let patterns = [
+            Regex::new(r#"VERSION_ID="(?P<version>[^"]+)""#).unwrap(),
+            Regex::new(r"VERSION_ID=(?P<version>[^\n]+)").unwrap(),
+        ];
+        for line in buffer.into_strings_iter() {
+            if let Some(v) = patterns
+                .iter()
+                .find_map(|re| re.captures(&line).and_then(|c| c.name("version")))
+            {
+                return Ok(Some(v.as_str().to_string()));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("embeddedconfig/rootfs.cpio", Some("3.1.14")),
+            ("embeddedconfig/initramfs.cpio.gz", Some("2021.02.4")),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::EmbeddedConfig)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+}
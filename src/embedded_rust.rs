@@ -0,0 +1,79 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{strings::IntoStringsIter, FindVersionError, VersionFinder};
+use regex::{bytes::Regex as BytesRegex, Regex};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+pub(crate) struct EmbeddedRust<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + Unpin> EmbeddedRust<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        EmbeddedRust { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for EmbeddedRust<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        // FIXME: Avoid reading the whole file
+        let mut buffer = Vec::new();
+        self.buf.read_to_end(&mut buffer).await?;
+
+        // Binaries built by rustc carry the standard library source paths
+        // used by the panic locations (e.g: '/rustc/<commit>/library/...').
+        let rustc = BytesRegex::new(r"/rustc/[0-9a-f]{40}/").unwrap();
+        if !rustc.is_match(&buffer) {
+            return Ok(None);
+        }
+
+        // Rust strings are not NUL terminated, so the version is usually
+        // packed together with other strings and needs a leading context.
+        let re = Regex::new(
+            r"(?:CARGO_PKG_VERSION=|[Vv]ersion:? )v?(?P<version>[0-9]+\.[0-9]+\.[0-9]+)",
+        )
+        .unwrap();
+        for line in buffer.into_strings_iter() {
+            if let Some(v) = re.captures(&line).and_then(|c| c.name("version")) {
+                return Ok(Some(v.as_str().to_string()));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/rust/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("firmware.bin", Some("0.7.1")),
+            ("cli.bin", Some("2.3.0")),
+            ("c-firmware.bin", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::EmbeddedRust)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+}
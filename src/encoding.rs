@@ -0,0 +1,264 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::FindVersionError;
+use regex::Regex;
+use std::str;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+// Size of each read looking for the version
+const CHUNK_SIZE: usize = 0x1000;
+
+// Amount of bytes kept among reads, which must be longer than the longest
+// version string, and even to keep the alignment of UTF-16 code units
+const OVERLAP: usize = 0x200;
+
+/// Text encoding used by the strings stored in a binary.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Encoding {
+    /// Plain ASCII, or any other single byte encoding.
+    Ascii,
+    /// UTF-8 with multi-byte sequences.
+    Utf8,
+    /// UTF-16, little-endian.
+    Utf16Le,
+    /// UTF-16, big-endian.
+    Utf16Be,
+    /// ISO-8859-1 (Latin-1).
+    Latin1,
+    /// The encoding could not be detected (e.g: empty buffer).
+    Unknown,
+}
+
+/// Guess the text encoding used by the given buffer.
+///
+/// Latin text encoded as UTF-16 has the high byte of most code units set to
+/// `0x00`, so when more than 10% of the bytes are `0x00` at only one of the
+/// byte positions of the code units, the buffer is taken as UTF-16. Otherwise,
+/// it is taken as UTF-8 when it is valid UTF-8 holding multi-byte sequences,
+/// falling back to ASCII.
+pub fn detect_encoding(buf: &[u8]) -> Encoding {
+    if buf.is_empty() {
+        return Encoding::Unknown;
+    }
+
+    let zeros_at = |parity| {
+        buf.iter()
+            .skip(parity)
+            .step_by(2)
+            .filter(|&&b| b == 0)
+            .count()
+    };
+    let threshold = buf.len() / 10;
+    match (zeros_at(0) > threshold, zeros_at(1) > threshold) {
+        (false, true) => return Encoding::Utf16Le,
+        (true, false) => return Encoding::Utf16Be,
+        _ => {}
+    }
+
+    if !buf.is_ascii() && str::from_utf8(buf).is_ok() {
+        return Encoding::Utf8;
+    }
+
+    Encoding::Ascii
+}
+
+/// Decode the buffer to UTF-8, guessing the encoding when it is unknown.
+///
+/// Invalid sequences are replaced by `U+FFFD`, and a trailing odd byte of
+/// UTF-16 buffers is dropped.
+pub(crate) fn decode(buf: &[u8], encoding: Encoding) -> String {
+    let utf16 = |f: fn([u8; 2]) -> u16| {
+        char::decode_utf16(buf.chunks_exact(2).map(|c| f([c[0], c[1]])))
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    };
+
+    match encoding {
+        Encoding::Utf8 => String::from_utf8_lossy(buf).into_owned(),
+        Encoding::Utf16Le => utf16(u16::from_le_bytes),
+        Encoding::Utf16Be => utf16(u16::from_be_bytes),
+        // Latin-1 is the first block of Unicode, so bytes map to the
+        // characters of the same code point
+        Encoding::Ascii | Encoding::Latin1 => buf.iter().copied().map(char::from).collect(),
+        Encoding::Unknown => match detect_encoding(buf) {
+            Encoding::Unknown => String::new(),
+            encoding => decode(buf, encoding),
+        },
+    }
+}
+
+/// Decode the buffer to UTF-8 as done by [`decode`], once for each alignment
+/// the strings may have: UTF-16 strings may start at odd offsets, when
+/// following a string of odd length, so both are tried for it.
+pub(crate) fn decode_aligned(buf: &[u8], encoding: Encoding) -> Vec<String> {
+    match encoding {
+        Encoding::Utf16Le | Encoding::Utf16Be => (0..2)
+            .map(|align| decode(buf.get(align..).unwrap_or_default(), encoding))
+            .collect(),
+        _ => vec![decode(buf, encoding)],
+    }
+}
+
+/// Look for the first capture group of the pattern in the strings of the
+/// binary, reading it in chunks and decoding them with the given encoding,
+/// which is guessed from the first chunk when unknown.
+///
+/// A string longer than the overlap among reads is missed when it spans two
+/// of them.
+pub(crate) async fn find_version<R: AsyncRead + Unpin>(
+    buffer: &mut R,
+    re: &Regex,
+    mut encoding: Encoding,
+) -> Result<Option<String>, FindVersionError> {
+    let mut chunk = vec![0; CHUNK_SIZE];
+    let mut data = Vec::with_capacity(CHUNK_SIZE + OVERLAP);
+    // Whether the data starts at the start of the binary, otherwise its
+    // first string may be the tail of a longer one
+    let mut start = true;
+    loop {
+        let n = buffer.read(&mut chunk).await?;
+        debug!("read {} bytes looking for encoded strings", n);
+        data.extend_from_slice(&chunk[..n]);
+        let eof = n == 0;
+
+        if encoding == Encoding::Unknown {
+            encoding = detect_encoding(&data);
+        }
+
+        for text in decode_aligned(&data, encoding) {
+            // The last string may continue in the next read
+            let strings = text.split(char::is_control).collect::<Vec<_>>();
+            let end = match eof {
+                true => strings.len(),
+                false => strings.len() - 1,
+            };
+            let version = strings
+                .get(usize::from(!start)..end)
+                .unwrap_or_default()
+                .iter()
+                .find_map(|s| re.captures(s).and_then(|c| c.get(1)));
+            if let Some(version) = version {
+                return Ok(Some(version.as_str().to_string()));
+            }
+        }
+
+        if eof {
+            return Ok(None);
+        }
+
+        // Keep the tail for the next read, dropping an even amount of bytes
+        // so the UTF-16 alignment is kept
+        let drop = data.len().saturating_sub(OVERLAP) & !1;
+        if drop > 0 {
+            data.drain(..drop);
+            start = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn utf16(s: &str, f: fn(u16) -> [u8; 2]) -> Vec<u8> {
+        s.encode_utf16().flat_map(f).collect()
+    }
+
+    #[test]
+    fn detect() {
+        let text = "U-Boot 2020.10 (Oct 30 2020 - 12:00:00)";
+
+        assert_eq!(detect_encoding(b""), Encoding::Unknown);
+        assert_eq!(detect_encoding(text.as_bytes()), Encoding::Ascii);
+        assert_eq!(detect_encoding("versión 1.2.3".as_bytes()), Encoding::Utf8);
+        assert_eq!(
+            detect_encoding(&utf16(text, u16::to_le_bytes)),
+            Encoding::Utf16Le
+        );
+        assert_eq!(
+            detect_encoding(&utf16(text, u16::to_be_bytes)),
+            Encoding::Utf16Be
+        );
+    }
+
+    #[test]
+    fn decoding() {
+        let text = "versión 1.2.3";
+
+        assert_eq!(decode(text.as_bytes(), Encoding::Utf8), text);
+        assert_eq!(
+            decode(&utf16(text, u16::to_le_bytes), Encoding::Utf16Le),
+            text
+        );
+        assert_eq!(
+            decode(&utf16(text, u16::to_be_bytes), Encoding::Utf16Be),
+            text
+        );
+        assert_eq!(decode(b"versi\xf3n 1.2.3", Encoding::Latin1), text);
+        assert_eq!(
+            decode(&utf16(text, u16::to_le_bytes), Encoding::Unknown),
+            text
+        );
+
+        // Unpaired surrogate and trailing odd byte
+        assert_eq!(decode(b"v\0\x00\xd81\0\0", Encoding::Utf16Le), "v\u{fffd}1");
+    }
+
+    #[tokio::test]
+    async fn version() {
+        use crate::version_with_encoding;
+
+        let encoded = |prefix| {
+            let mut data = vec![0xff; prefix];
+            data.extend(utf16("\0Firmware version 2.4.1-r3\0", u16::to_le_bytes));
+            data.extend_from_slice(&[0xff; 0x21]);
+            data
+        };
+
+        // UTF-16 strings are found at both even and odd offsets, so
+        // little-endian strings are found as big-endian ones shifted by a byte
+        for prefix in [0x20, 0x21] {
+            for (encoding, v) in [
+                (Encoding::Utf16Le, Some("2.4.1-r3")),
+                (Encoding::Unknown, Some("2.4.1-r3")),
+                (Encoding::Utf16Be, Some("2.4.1-r3")),
+                (Encoding::Latin1, None),
+            ] {
+                assert_eq!(
+                    version_with_encoding(
+                        &mut std::io::Cursor::new(encoded(prefix)),
+                        r"version (\d+\.\d+\.\d+\S*)",
+                        encoding
+                    )
+                    .await
+                    .unwrap(),
+                    v.map(|v| v.to_string()),
+                    "{:?} at {:#x}",
+                    encoding,
+                    prefix
+                );
+            }
+        }
+
+        // String spanning two reads
+        assert_eq!(
+            version_with_encoding(
+                &mut std::io::Cursor::new(encoded(CHUNK_SIZE - 0x11)),
+                r"version (\d+\.\d+\.\d+\S*)",
+                Encoding::Utf16Le
+            )
+            .await
+            .unwrap(),
+            Some("2.4.1-r3".to_string())
+        );
+    }
+
+    #[test]
+    fn binary() {
+        // Invalid UTF-8 and zero padded data are taken as ASCII
+        assert_eq!(detect_encoding(b"version\xff\xfe1.0"), Encoding::Ascii);
+        assert_eq!(detect_encoding(&[0; 64]), Encoding::Ascii);
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use std::{fmt, io};
+
+/// Errors reported while looking for the version.
+#[derive(Debug)]
+pub enum FindVersionError {
+    /// Failed to read or seek the binary.
+    Io(io::Error),
+    /// The given pattern is not a valid regular expression.
+    InvalidPattern(regex::Error),
+    /// The binary does not match the expected checksum.
+    IntegrityCheckFailed,
+}
+
+impl fmt::Display for FindVersionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FindVersionError::Io(e) => write!(f, "failed to read the binary: {}", e),
+            FindVersionError::InvalidPattern(e) => write!(f, "invalid pattern: {}", e),
+            FindVersionError::IntegrityCheckFailed => write!(f, "binary integrity check failed"),
+        }
+    }
+}
+
+impl std::error::Error for FindVersionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FindVersionError::Io(e) => Some(e),
+            FindVersionError::InvalidPattern(e) => Some(e),
+            FindVersionError::IntegrityCheckFailed => None,
+        }
+    }
+}
+
+impl From<io::Error> for FindVersionError {
+    fn from(e: io::Error) -> Self {
+        FindVersionError::Io(e)
+    }
+}
+
+impl From<regex::Error> for FindVersionError {
+    fn from(e: regex::Error) -> Self {
+        FindVersionError::InvalidPattern(e)
+    }
+}
+
+/// Error parsing a [`BinaryKind`](crate::BinaryKind) from an unknown name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownBinaryKind(pub String);
+
+impl fmt::Display for UnknownBinaryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown binary kind: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownBinaryKind {}
+
+/// Treat reaching the end of the binary while parsing it as the version not
+/// being found, as it is not laid out as the binary kind expects.
+pub(crate) fn eof_as_not_found<T>(
+    result: Result<Option<T>, FindVersionError>,
+) -> Result<Option<T>, FindVersionError> {
+    match result {
+        Err(FindVersionError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        result => result,
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::VersionFinder;
+use std::fmt;
+use tokio::io::{AsyncRead, AsyncSeek};
+
+/// Reader a [`VersionFinderFactory`] creates its finders for, implemented
+/// by every seekable async reader.
+pub trait AsyncReadSeek: AsyncRead + AsyncSeek + Unpin {}
+
+impl<T: AsyncRead + AsyncSeek + Unpin + ?Sized> AsyncReadSeek for T {}
+
+/// Factory of version finders for binary kinds provided outside of the
+/// crate, used through [`BinaryKind::Other`](crate::BinaryKind::Other).
+///
+/// ```
+/// use find_binary_version::{
+///     version, AsyncReadSeek, BinaryKind, FindVersionError, VersionFinder, VersionFinderFactory,
+/// };
+/// use std::sync::Arc;
+/// use tokio::io::AsyncReadExt;
+///
+/// struct Magic<'a>(&'a mut dyn AsyncReadSeek);
+///
+/// #[async_trait::async_trait(?Send)]
+/// impl VersionFinder for Magic<'_> {
+///     async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+///         let mut magic = [0; 4];
+///         self.0.read_exact(&mut magic).await?;
+///         Ok((&magic == b"MGC1").then(|| "1.0".to_string()))
+///     }
+/// }
+///
+/// #[derive(Debug)]
+/// struct MagicFactory;
+///
+/// impl VersionFinderFactory for MagicFactory {
+///     fn create<'a>(&self, buf: &'a mut dyn AsyncReadSeek) -> Box<dyn VersionFinder + 'a> {
+///         Box::new(Magic(buf))
+///     }
+/// }
+///
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let kind = BinaryKind::Other(Arc::new(MagicFactory));
+/// let mut binary = std::io::Cursor::new(b"MGC1 binary");
+/// assert_eq!(version(&mut binary, kind).await.unwrap(), Some("1.0".to_string()));
+/// # });
+/// ```
+pub trait VersionFinderFactory: fmt::Debug + Send + Sync {
+    /// Create the version finder reading the given binary.
+    fn create<'a>(&self, buf: &'a mut dyn AsyncReadSeek) -> Box<dyn VersionFinder + 'a>;
+
+    /// Name of the binary kind, used when it is displayed.
+    fn name(&self) -> &str {
+        "other"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AsyncReadSeek, VersionFinderFactory};
+    use crate::{uboot::UBoot, version, version_all, BinaryKind, FindVersionError, VersionFinder};
+    use std::sync::Arc;
+
+    struct VendorUBoot<'a>(&'a mut dyn AsyncReadSeek);
+
+    #[async_trait::async_trait(?Send)]
+    impl VersionFinder for VendorUBoot<'_> {
+        async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+            UBoot::from_reader(&mut self.0).get_version().await
+        }
+    }
+
+    #[derive(Debug)]
+    struct UBootFactory;
+
+    impl VersionFinderFactory for UBootFactory {
+        fn create<'a>(&self, buf: &'a mut dyn AsyncReadSeek) -> Box<dyn VersionFinder + 'a> {
+            Box::new(VendorUBoot(buf))
+        }
+
+        fn name(&self) -> &str {
+            "vendor-u-boot"
+        }
+    }
+
+    #[tokio::test]
+    async fn other() {
+        let kind = BinaryKind::Other(Arc::new(UBootFactory));
+        assert_eq!(kind.to_string(), "vendor-u-boot");
+
+        let data = std::fs::read("tests/fixtures/uboot/arm-spl").unwrap();
+        assert_eq!(
+            version(&mut std::io::Cursor::new(&data), kind.clone())
+                .await
+                .unwrap(),
+            Some("2017.11+fslc+ga07698f".to_string()),
+        );
+        assert_eq!(
+            version_all(&mut &data[..], kind).await.unwrap(),
+            vec!["2017.11+fslc+ga07698f".to_string()],
+        );
+    }
+}
@@ -0,0 +1,251 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{uboot::UBOOT_VERSION_PATTERN, FindVersionError, VersionFinder};
+use regex::Regex;
+use std::{io::SeekFrom, str};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+// Flattened Device Tree Magic Number
+pub(crate) const FDT_MAGIC: u32 = 0xD00D_FEED;
+
+// Flattened Device Tree header size (version 17, version 16 lacking its last
+// field)
+const FDT_HEADER_SIZE: usize = 0x28;
+
+// Structure block tokens
+const FDT_BEGIN_NODE: u32 = 0x1;
+const FDT_END_NODE: u32 = 0x2;
+const FDT_PROP: u32 = 0x3;
+const FDT_NOP: u32 = 0x4;
+const FDT_END: u32 = 0x9;
+
+// Sub-image holding the kernel ("kernel-1" in newer mkimage versions)
+const FIT_KERNEL_NODES: &[&[u8]] = &[b"kernel@1", b"kernel-1"];
+
+#[derive(Debug, Clone, Copy)]
+enum Endian {
+    Big,
+    Little,
+}
+
+/// Sub-image of the `/images` node.
+#[derive(Debug, Default)]
+struct Image<'a> {
+    name: &'a [u8],
+    version: Option<&'a str>,
+    description: Option<&'a str>,
+}
+
+pub(crate) fn cstr(data: &[u8]) -> Option<&[u8]> {
+    data.iter().position(|&b| b == 0).map(|n| &data[..n])
+}
+
+fn align(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+/// Walk the structure block of the flattened device tree, calling `visit`
+/// with the path of each node as it begins, and with the path of the node and
+/// the name and value of each of its properties. The walk stops as soon as
+/// `visit` returns `None`.
+///
+/// Device trees are always stored as big-endian, but images converted by
+/// little-endian tools are accepted too, telling the byte order from the
+/// magic number.
+pub(crate) fn walk<'a>(
+    data: &'a [u8],
+    mut visit: impl FnMut(&[&'a [u8]], Option<(&'a [u8], &'a [u8])>) -> Option<()>,
+) -> Option<()> {
+    // Taken from: https://devicetree-specification.readthedocs.io/en/latest/chapter5-flattened-format.html
+    //
+    // Offset  Size  Name               Meaning
+    // 0000    4     magic              Magic number, 0xd00dfeed
+    // 0004    4     totalsize          Size of the whole blob
+    // 0008    4     off_dt_struct      Offset of the structure block
+    // 000C    4     off_dt_strings     Offset of the strings block
+    // 0010    4     off_mem_rsvmap     Offset of the memory reservation block
+    // 0014    4     version            Version of the blob format
+    // 0018    4     last_comp_version  Lowest compatible version
+    // 001C    4     boot_cpuid_phys    Physical ID of the boot CPU
+    // 0020    4     size_dt_strings    Size of the strings block
+    // 0024    4     size_dt_struct     Size of the structure block (version 17)
+    let magic: [u8; 4] = data.get(..4)?.try_into().ok()?;
+    let endian = if u32::from_be_bytes(magic) == FDT_MAGIC {
+        Endian::Big
+    } else if u32::from_le_bytes(magic) == FDT_MAGIC {
+        Endian::Little
+    } else {
+        return None;
+    };
+    let u32_at = |offset: usize| -> Option<u32> {
+        let bytes = data.get(offset..offset.checked_add(4)?)?.try_into().ok()?;
+        Some(match endian {
+            Endian::Big => u32::from_be_bytes(bytes),
+            Endian::Little => u32::from_le_bytes(bytes),
+        })
+    };
+
+    let strings = data.get(u32_at(0x0C)? as usize..)?;
+    let mut offset = u32_at(0x08)? as usize;
+    let mut path = Vec::new();
+    loop {
+        let token = u32_at(offset)?;
+        offset += 4;
+        match token {
+            FDT_BEGIN_NODE => {
+                let name = cstr(data.get(offset..)?)?;
+                offset = align(offset + name.len() + 1);
+                path.push(name);
+                visit(&path, None)?;
+            }
+            FDT_END_NODE => {
+                path.pop()?;
+            }
+            FDT_PROP => {
+                let len = u32_at(offset)? as usize;
+                let name = cstr(strings.get(u32_at(offset + 4)? as usize..)?)?;
+                let value = data.get(offset + 8..(offset + 8).checked_add(len)?)?;
+                offset = align(offset + 8 + len);
+                visit(&path, Some((name, value)))?;
+            }
+            FDT_NOP => {}
+            FDT_END => return Some(()),
+            _ => return None,
+        }
+    }
+}
+
+/// Read the whole flattened device tree, whose size is told by its header.
+///
+/// `None` is returned when the binary is not a flattened device tree.
+pub(crate) async fn read_fdt<R: AsyncRead + AsyncSeek + Unpin>(
+    buf: &mut R,
+) -> Result<Option<Vec<u8>>, FindVersionError> {
+    let mut header = [0; FDT_HEADER_SIZE];
+    buf.seek(SeekFrom::Start(0)).await?;
+    buf.read_exact(&mut header).await?;
+
+    let totalsize = match (
+        u32::from_be_bytes(header[..4].try_into().unwrap()),
+        u32::from_le_bytes(header[..4].try_into().unwrap()),
+    ) {
+        (FDT_MAGIC, _) => u32::from_be_bytes(header[4..8].try_into().unwrap()),
+        (_, FDT_MAGIC) => u32::from_le_bytes(header[4..8].try_into().unwrap()),
+        _ => return Ok(None),
+    };
+    debug!("read FDT header with total size {:#x}", totalsize);
+
+    let mut data = header.to_vec();
+    buf.take(u64::from(totalsize).saturating_sub(FDT_HEADER_SIZE as u64))
+        .read_to_end(&mut data)
+        .await?;
+    Ok(Some(data))
+}
+
+/// Collect the sub-images of the `/images` node of the FIT.
+fn parse_images(data: &[u8]) -> Option<Vec<Image<'_>>> {
+    let mut images = Vec::new();
+    walk(data, |path, property| {
+        // The root node has an empty name, so the sub-images are found at
+        // /images/<name>
+        if path.len() != 3 || path[1] != b"images" {
+            return Some(());
+        }
+
+        match property {
+            None => images.push(Image {
+                name: path[2],
+                ..Image::default()
+            }),
+            Some((name, value)) => {
+                let value = str::from_utf8(cstr(value).unwrap_or(value)).ok();
+                let image = images.last_mut()?;
+                match name {
+                    b"version" => image.version = value,
+                    b"description" => image.description = value,
+                    _ => {}
+                }
+            }
+        }
+        Some(())
+    })?;
+
+    Some(images)
+}
+
+pub(crate) struct Fit<'a, R: AsyncRead + AsyncSeek + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> Fit<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        Fit { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> VersionFinder for Fit<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        let data = match read_fdt(self.buf).await? {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+
+        let images = match parse_images(&data) {
+            Some(images) => images,
+            None => return Ok(None),
+        };
+
+        if let Some(version) = images
+            .iter()
+            .filter(|i| FIT_KERNEL_NODES.contains(&i.name))
+            .find_map(|i| i.version)
+        {
+            return Ok(Some(version.to_string()));
+        }
+
+        let re = Regex::new(UBOOT_VERSION_PATTERN).unwrap();
+        Ok(images
+            .iter()
+            .filter_map(|i| i.description)
+            .find_map(|d| re.captures(d).and_then(|c| c.name("version")))
+            .map(|v| v.as_str().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("fit/kernel.itb", Some("5.15.32")),
+            ("fit/kernel-le.itb", Some("5.15.32")),
+            ("fit/u-boot.itb", Some("2022.04")),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::FIT)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+                "{} version mismatch",
+                f
+            );
+        }
+    }
+}
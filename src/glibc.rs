@@ -0,0 +1,75 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{builder::Config, custom::Custom, FindVersionError, VersionFinder};
+use tokio::io::AsyncRead;
+
+/// GNU C Library version string, as printed when `libc.so.6` is run (e.g:
+/// "GNU C Library (Debian GLIBC 2.36-9+deb12u4) stable release version
+/// 2.36."), whose `version` group is the library version.
+pub const GLIBC_VERSION_PATTERN: &str =
+    r"GNU C Library .*\bversion (?P<version>\d+\.\d+(?:\.\d+)?)";
+
+pub(crate) struct GlibcLibrary<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+    config: Config<'a>,
+}
+
+impl<'a, R: AsyncRead + Unpin> GlibcLibrary<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        GlibcLibrary {
+            buf,
+            config: Config::default(),
+        }
+    }
+
+    pub(crate) fn with_config(self, config: Config<'a>) -> Self {
+        GlibcLibrary { config, ..self }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for GlibcLibrary<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        Custom::from_reader(self.buf, GLIBC_VERSION_PATTERN)
+            .with_config(self.config)
+            .with_group("version")
+            .get_version()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("libc/glibc.so", Some("2.36")),
+            ("libc/musl.so", None),
+            ("busybox/busybox", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::GlibcLibrary)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+                "{} version mismatch",
+                f
+            );
+        }
+    }
+}
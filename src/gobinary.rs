@@ -0,0 +1,129 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{elf_utils::ELF_MAGIC, FindVersionError, VersionFinder};
+use regex::Regex;
+use std::str;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+// Go build information Magic Number
+const BUILDINFO_MAGIC: &[u8] = b"\xff Go buildinf:";
+
+// Go build information header size, after which the version is stored
+const BUILDINFO_HEADER_SIZE: usize = 0x20;
+
+// Executable formats Go binaries are built as: ELF, PE and Mach-O (32-bit
+// and 64-bit, in either endianness)
+const EXECUTABLE_MAGICS: &[&[u8]] = &[
+    ELF_MAGIC,
+    b"MZ",
+    b"\xfe\xed\xfa\xce",
+    b"\xce\xfa\xed\xfe",
+    b"\xfe\xed\xfa\xcf",
+    b"\xcf\xfa\xed\xfe",
+];
+
+pub(crate) struct GoBinary<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + Unpin> GoBinary<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        GoBinary { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for GoBinary<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        let mut magic = [0; 4];
+        self.buf.read_exact(&mut magic).await?;
+        if !EXECUTABLE_MAGICS.iter().any(|m| magic.starts_with(m)) {
+            return Ok(None);
+        }
+
+        // FIXME: Avoid reading the whole file
+        let mut data = magic.to_vec();
+        self.buf.read_to_end(&mut data).await?;
+
+        // Taken from: https://go.dev/src/debug/buildinfo/buildinfo.go
+        //
+        // Offset  Size  Name      Meaning
+        // 0000    14    magic     "\xff Go buildinf:"
+        // 000E    1     ptr_size  Size of the pointers, in bytes
+        // 000F    1     flags     Endianness and, since Go 1.18, inline strings
+        // 0020    ...   version   Length prefixed toolchain version
+        //
+        // Only the inline strings are looked at, as in older versions the
+        // header points to the version, which is stored apart from it.
+        let start = match data
+            .windows(BUILDINFO_MAGIC.len())
+            .position(|w| w == BUILDINFO_MAGIC)
+        {
+            Some(position) => position + BUILDINFO_HEADER_SIZE,
+            None => return Ok(None),
+        };
+
+        // The length is a varint, taking a single byte for any version string
+        // shorter than 128 bytes
+        let version = data
+            .get(start)
+            .filter(|&&len| len < 0x80)
+            .and_then(|&len| data.get(start + 1..start + 1 + usize::from(len)))
+            .and_then(|v| str::from_utf8(v).ok());
+        debug!("read Go build information version {:?}", version);
+
+        let re = Regex::new(r"^go(?P<version>1\.\d+(?:\.\d+)?)").unwrap();
+        Ok(version
+            .and_then(|v| re.captures(v))
+            .and_then(|c| c.name("version"))
+            .map(|v| v.as_str().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("go/hello", Some("1.21.5")),
+            ("rust/cli.bin", None),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::GoBinary)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn not_adjacent() {
+        // The runtime strings mentioning a Go version are not used
+        let mut data = std::fs::read("tests/fixtures/go/hello").unwrap();
+        data[0x2000] = 0;
+
+        assert_eq!(
+            version(&mut std::io::Cursor::new(data), BinaryKind::GoBinary)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+}
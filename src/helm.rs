@@ -0,0 +1,111 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    archive::read_archive_file, strings::IntoStringsIter, FindVersionError, VersionFinder,
+};
+use regex::Regex;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+// Gzip Magic Number, as Helm charts are packaged as .tgz archives
+const GZIP_MAGIC: &[u8] = b"\x1f\x8b";
+
+/// Extract the top-level Chart.yaml from a packaged chart. The subcharts
+/// (e.g: 'mychart/charts/common/Chart.yaml') are ignored.
+fn read_chart_yaml(archive: &[u8]) -> Option<Vec<u8>> {
+    read_archive_file(archive, |f| {
+        matches!(f.split('/').collect::<Vec<_>>()[..], [_, "Chart.yaml"])
+    })
+}
+
+fn find_chart_version(chart: &[u8]) -> Option<String> {
+    // The chart version is preferred, using the application version as
+    // fallback.
+    let patterns = [
+        Regex::new(r"^version:\s*(?P<version>[^\n]+)").unwrap(),
+        Regex::new(r"^appVersion:\s*(?P<version>[^\n]+)").unwrap(),
+    ];
+    for re in &patterns {
+        for line in chart.into_strings_iter() {
+            if let Some(v) = re.captures(&line).and_then(|c| c.name("version")) {
+                return Some(v.as_str().trim().trim_matches('"').to_string());
+            }
+        }
+    }
+
+    None
+}
+
+pub(crate) struct Helm<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + Unpin> Helm<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        Helm { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for Helm<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        // FIXME: Avoid reading the whole file
+        let mut buffer = Vec::new();
+        self.buf.read_to_end(&mut buffer).await?;
+
+        if buffer.starts_with(GZIP_MAGIC) {
+            if let Some(chart) = read_chart_yaml(&buffer) {
+                return Ok(find_chart_version(&chart));
+            }
+        }
+
+        // Not a chart archive, or it could not be extracted, so look for the
+        // Chart.yaml contents in the raw bytes.
+        Ok(find_chart_version(&buffer))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("helm/nginx-9.4.1.tgz", Some("9.4.1")),
+            ("helm/legacy.tgz", Some("2.0.3")),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::Helm)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn raw() {
+        let chart = b"apiVersion: v2\nname: raw\nversion: 0.3.0\n";
+
+        assert_eq!(
+            version(&mut std::io::Cursor::new(chart), BinaryKind::Helm)
+                .await
+                .unwrap(),
+            Some("0.3.0".to_string())
+        );
+    }
+}
@@ -0,0 +1,145 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::FindVersionError;
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use std::io::SeekFrom;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+/// Checksum the whole binary must match before looking for its version.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum IntegrityCheck {
+    /// Do not verify the binary.
+    None,
+    /// CRC32 (IEEE) checksum of the binary.
+    Crc32(u32),
+    /// SHA-256 digest of the binary.
+    Sha256([u8; 32]),
+    /// MD5 digest of the binary.
+    Md5([u8; 16]),
+}
+
+enum Hasher {
+    Crc32(crc32fast::Hasher),
+    Sha256(Sha256),
+    Md5(Md5),
+}
+
+impl Hasher {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Crc32(h) => h.update(data),
+            Hasher::Sha256(h) => h.update(data),
+            Hasher::Md5(h) => h.update(data),
+        }
+    }
+}
+
+/// Verify the whole binary contents against the given checksum, leaving the
+/// reader at the begin of the binary.
+pub(crate) async fn verify<R: AsyncRead + AsyncSeek + Unpin>(
+    buf: &mut R,
+    check: IntegrityCheck,
+) -> Result<(), FindVersionError> {
+    let mut hasher = match check {
+        IntegrityCheck::None => return Ok(()),
+        IntegrityCheck::Crc32(_) => Hasher::Crc32(crc32fast::Hasher::new()),
+        IntegrityCheck::Sha256(_) => Hasher::Sha256(Sha256::new()),
+        IntegrityCheck::Md5(_) => Hasher::Md5(Md5::new()),
+    };
+
+    buf.seek(SeekFrom::Start(0)).await?;
+    let mut buffer = [0; 0x2000];
+    loop {
+        let n = buf.read(&mut buffer).await?;
+        if n == 0 {
+            break;
+        }
+
+        hasher.update(&buffer[..n]);
+    }
+    buf.seek(SeekFrom::Start(0)).await?;
+
+    let valid = match (hasher, check) {
+        (Hasher::Crc32(h), IntegrityCheck::Crc32(expected)) => h.finalize() == expected,
+        (Hasher::Sha256(h), IntegrityCheck::Sha256(expected)) => h.finalize()[..] == expected,
+        (Hasher::Md5(h), IntegrityCheck::Md5(expected)) => h.finalize()[..] == expected,
+        _ => false,
+    };
+    if !valid {
+        return Err(FindVersionError::IntegrityCheckFailed);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version_with_integrity_check, BinaryKind, FindVersionError, IntegrityCheck};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/uboot/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    // Checksums of the uboot/arm-spl fixture
+    const CRC32: u32 = 0x3f01_93f0;
+    const SHA256: &str = "3af3021f80a4fc798447589a2ad0ce616a5087eb595e435cc37f7214b197a996";
+    const MD5: &str = "48aea12319bfd93953b8c3b1f439ac46";
+
+    fn digest<const N: usize>(hex: &str) -> [u8; N] {
+        let mut digest = [0; N];
+        for (i, b) in digest.iter_mut().enumerate() {
+            *b = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap();
+        }
+        digest
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for check in &[
+            IntegrityCheck::None,
+            IntegrityCheck::Crc32(CRC32),
+            IntegrityCheck::Sha256(digest(SHA256)),
+            IntegrityCheck::Md5(digest(MD5)),
+        ] {
+            assert_eq!(
+                version_with_integrity_check(
+                    &mut fixture("arm-spl").await,
+                    BinaryKind::UBoot,
+                    *check
+                )
+                .await
+                .unwrap(),
+                Some("2017.11+fslc+ga07698f".to_string()),
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid() {
+        for check in &[
+            IntegrityCheck::Crc32(!CRC32),
+            IntegrityCheck::Sha256([0; 32]),
+            IntegrityCheck::Md5([0; 16]),
+        ] {
+            assert!(matches!(
+                version_with_integrity_check(
+                    &mut fixture("arm-spl").await,
+                    BinaryKind::UBoot,
+                    *check
+                )
+                .await,
+                Err(FindVersionError::IntegrityCheckFailed)
+            ));
+        }
+    }
+}
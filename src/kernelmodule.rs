@@ -0,0 +1,118 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    elf_utils::{parse_elf_sections, read_elf_image},
+    strings::IntoStringsIter,
+    FindVersionError, VersionFinder,
+};
+use regex::Regex;
+use std::str;
+use tokio::io::AsyncRead;
+
+// Section holding the module information, as key=value pairs
+const MODINFO_SECTION: &str = ".modinfo";
+
+// Module information key holding the kernel the module was built for
+const VERMAGIC_KEY: &str = "vermagic=";
+
+/// Extract the kernel version from the vermagic value (e.g: "5.15.0-generic
+/// SMP mod_unload").
+fn vermagic_version(vermagic: &str) -> Option<String> {
+    let re = Regex::new(r"^(?P<version>\d+\.\d+[^\s]+)").unwrap();
+    re.captures(vermagic)
+        .and_then(|c| c.name("version"))
+        .map(|v| v.as_str().to_string())
+}
+
+pub(crate) struct KernelModule<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + Unpin> KernelModule<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        KernelModule { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for KernelModule<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        // Modules are often shipped XZ compressed (e.g: hello.ko.xz)
+        let data = match read_elf_image(self.buf).await? {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+
+        let modinfo = parse_elf_sections(&data)
+            .into_iter()
+            .find(|s| s.name == MODINFO_SECTION)
+            .and_then(|s| {
+                let start = usize::try_from(s.offset).ok()?;
+                let end = usize::try_from(s.offset.checked_add(s.size)?).ok()?;
+                data.get(start..end)
+            });
+
+        if let Some(modinfo) = modinfo {
+            debug!("read {} bytes of module information", modinfo.len());
+            return Ok(modinfo
+                .split(|&b| b == 0)
+                .filter_map(|s| str::from_utf8(s).ok())
+                .find_map(|s| s.strip_prefix(VERMAGIC_KEY))
+                .and_then(vermagic_version));
+        }
+
+        // Without the section (e.g: a stripped or truncated module), look for
+        // the vermagic string anywhere in the binary
+        Ok(data[..]
+            .into_strings_iter()
+            .find_map(|s| s.strip_prefix(VERMAGIC_KEY).and_then(vermagic_version)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("kernelmodule/hello.ko", Some("5.15.0-generic")),
+            ("kernelmodule/hello.ko.xz", Some("5.15.0-generic")),
+            ("elf/x86_64-comment.o", None),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::KernelModule)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+                "{} version mismatch",
+                f
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn raw_scan() {
+        let data = b"\0\0license=GPL\0vermagic=5.10.0-9-arm64 SMP mod_unload aarch64\0".to_vec();
+        assert_eq!(
+            version(&mut std::io::Cursor::new(data), BinaryKind::KernelModule)
+                .await
+                .unwrap(),
+            Some("5.10.0-9-arm64".to_string()),
+        );
+    }
+}
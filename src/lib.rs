@@ -34,49 +34,1675 @@
 //!
 //! * U-Boot
 //! * LinuxKernel
+//! * Android OTA payload
+//! * U-Boot legacy image (uImage) header
+//! * Embedded package metadata (`package.json`, `Cargo.toml` or `setup.py`)
+//! * UEFI capsule update
+//! * U-Boot script (raw text or `.scr` uImage)
+//! * Distribution release information (`os-release`)
+//! * Helm chart archive
+//! * Microcontroller firmware (raw, Intel HEX or Motorola S-record)
+//! * OpenWrt package (`.ipk`)
+//! * XZ compressed Linux Kernel
+//! * Python wheel (`.whl`)
+//! * OCI/Docker container image (`docker save` archive or layer)
+//! * Rust binaries embedding the crate version
+//! * Barebox bootloader
+//! * U-Boot Flattened Image Tree (FIT)
+//! * ELF binaries (`.comment` section)
+//! * Android boot image (`boot.img`)
+//! * Arm Trusted Firmware (BL2/BL31 images or FIP)
+//! * Go binaries (toolchain version)
+//! * UEFI firmware management (FMP) capsules (firmware version)
+//! * Linux kernel modules (`vermagic` module information)
+//! * Busybox binaries
+//! * OpenWrt sysupgrade images (`VERSION_CODE` metadata)
+//! * Device Tree Blob (DTB)
+//! * Raspberry Pi VideoCore firmware
+//! * U-Boot environment
+//! * GNU C Library (glibc) shared libraries
+//! * musl libc shared libraries
+//! * OpenSSL and BoringSSL libraries
+//! * Bare-metal ARM Cortex-M firmware
+//! * Raspberry Pi 4 bootloader EEPROM
+//! * SquashFS filesystem images
+//! * Windows PE binaries (`.exe` and `.dll`)
+//! * Zephyr RTOS applications
 //!
 //! Other formats are supported through the `version_with_pattern` function,
 //! which will look for a given regular expression on the given binary.
+//!
+//! ## Optional features
+//!
+//! * `async-std`: provide the `version_async_std` and
+//!   `version_with_pattern_async_std` functions, reading the binaries through
+//!   the `futures-io` traits used by the async-std and smol runtimes.
+//! * `json`: parse the container image configuration as JSON, instead of
+//!   looking for the version label in its raw contents.
+//! * `mmap` (Unix only): provide `version_mmap`, mapping the file in memory
+//!   instead of reading it, which is faster for large binaries.
+//! * `semver`: provide the `parse_version` and `compare_versions` functions,
+//!   mapping the versions found to SemVer versions.
+//! * `serde`: derive `Serialize` and `Deserialize` for `BinaryKind`, using
+//!   kebab-case names (e.g: `"u-boot"`), and for `VersionInfo`.
+//! * `sync` (enabled by default): provide the blocking `version_sync` and
+//!   `version_with_pattern_sync` functions, for use outside of an async
+//!   runtime.
+//! * `tracing`: emit `tracing` debug events for the offsets probed, the magic
+//!   numbers read and the patterns matched while looking for the version, and
+//!   trace events for each seek and read done while detecting the Linux kernel
+//!   format.
+
+// Forward to `tracing::debug!` when the tracing feature is enabled, expanding
+// to nothing otherwise.
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+    };
+}
+
+// Forward to `tracing::trace!`, as done for `debug!`, for the events too
+// frequent to be logged at the debug level (e.g: each seek). The arguments are
+// still used without the feature, so they are checked and need no allowance
+// for being unused.
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::trace!($($arg)*);
+        #[cfg(not(feature = "tracing"))]
+        {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
 
+mod android;
+mod androidota;
+mod archive;
+mod arm_kernel;
+mod atf;
+mod auto;
+mod barebox;
+#[cfg(feature = "sync")]
+mod blocking;
+mod builder;
+mod busybox;
+#[cfg(feature = "async-std")]
+mod compat;
+mod container_image;
+mod cortex_m;
 mod custom;
+mod detect;
+mod devicetree;
+mod efi_capsule;
+mod elf;
+mod elf_utils;
+mod embedded_config;
+mod embedded_rust;
+mod encoding;
+mod error;
+mod factory;
+mod fit;
+mod glibc;
+mod gobinary;
+mod helm;
+#[cfg(test)]
+#[path = "../tests/helpers/mod.rs"]
+mod helpers;
+mod integrity;
+mod kernelmodule;
 mod linuxkernel;
+mod microcontroller;
+#[cfg(all(unix, feature = "mmap"))]
+mod mmap;
+mod musl;
+mod openssl;
+mod openwrt;
+mod openwrt_package;
+mod packageinfo;
+mod pe;
+mod python_wheel;
+mod rpi_eeprom;
+#[cfg(feature = "semver")]
+mod semantic_version;
+mod squashfs;
 mod strings;
 mod uboot;
+mod uboot_env;
+mod uboot_script;
+mod uefi;
+mod uimage;
+mod version_info;
+mod videocore;
+mod x86kernel;
+mod xz_payload;
+mod zephyr;
 
-use crate::{custom::Custom, linuxkernel::LinuxKernel, uboot::UBoot};
-use tokio::io::{AsyncRead, AsyncSeek};
+#[cfg(feature = "sync")]
+pub use crate::blocking::{version_at_offset_sync, version_sync, version_with_pattern_sync};
+#[cfg(feature = "async-std")]
+pub use crate::compat::{version_async_std, version_with_pattern_async_std};
+#[cfg(all(unix, feature = "mmap"))]
+pub use crate::mmap::version_mmap;
+#[cfg(feature = "semver")]
+pub use crate::semantic_version::{compare_versions, parse_version, VersionOrdering};
+pub use crate::{
+    builder::{ConfiguredFinder, VersionFinderBuilder},
+    custom::{Custom, Pattern},
+    encoding::{detect_encoding, Encoding},
+    error::{FindVersionError, UnknownBinaryKind},
+    factory::{AsyncReadSeek, VersionFinderFactory},
+    glibc::GLIBC_VERSION_PATTERN,
+    integrity::IntegrityCheck,
+    linuxkernel::KernelArch,
+    musl::MUSL_VERSION_PATTERN,
+    openwrt::OpenWrtVersion,
+    strings::{IntoStringsIter, IsPrintable, Strings, StringsConfig, StringsUtf8},
+    uboot::{UBoot, UBootVariant},
+    uimage::UImageHeader,
+    version_info::VersionInfo,
+    zephyr::ZEPHYR_VERSION_PATTERN,
+};
 
-#[derive(Debug, Copy, Clone)]
+use crate::{
+    android::AndroidBoot,
+    androidota::AndroidOtaPayload,
+    arm_kernel::ArmKernel,
+    atf::ArmTrustedFirmware,
+    auto::Auto,
+    barebox::Barebox,
+    builder::Config,
+    busybox::Busybox,
+    container_image::ContainerImage,
+    cortex_m::CortexMFirmware,
+    devicetree::DeviceTree,
+    efi_capsule::EfiCapsule,
+    elf::ElfComment,
+    embedded_config::EmbeddedConfig,
+    embedded_rust::EmbeddedRust,
+    fit::Fit,
+    glibc::GlibcLibrary,
+    gobinary::GoBinary,
+    helm::Helm,
+    kernelmodule::KernelModule,
+    linuxkernel::{LinuxKernel, LinuxKernelAll},
+    microcontroller::MicrocontrollerFirmware,
+    musl::MuslLibrary,
+    openssl::OpenSSL,
+    openwrt::OpenWrt,
+    openwrt_package::OpenwrtPackage,
+    packageinfo::PackageInfo,
+    pe::WindowsPE,
+    python_wheel::PythonWheel,
+    rpi_eeprom::RPi4Eeprom,
+    squashfs::SquashFS,
+    uboot_env::UBootEnv,
+    uboot_script::UBootScript,
+    uefi::UefiCapsule,
+    uimage::UImage,
+    videocore::VideocoreFirmware,
+    x86kernel::X86Kernel,
+    xz_payload::XzPayload,
+    zephyr::ZephyrRTOS,
+};
+use futures_util::{
+    stream::{self, FuturesUnordered},
+    Stream, StreamExt, TryStreamExt,
+};
+use regex::Regex;
+use std::{
+    fmt,
+    future::Future,
+    io::{Cursor, SeekFrom},
+    iter,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    fs::File,
+    io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, BufReader},
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
 /// Define the binary kind to use for matching.
+#[non_exhaustive]
 pub enum BinaryKind {
     /// U-Boot binary kind.
     UBoot,
-    /// Linux Kernel binary kind.
+    /// Linux Kernel binary kind, trying all the supported architectures
+    /// (uImage, ARM zImage, x86, AArch64 Image, RISC-V Image, PowerPC ELF and
+    /// MIPS vmlinuz).
     LinuxKernel,
+    /// Android OTA payload (`payload.bin`) binary kind, using the build
+    /// fingerprint as version.
+    AndroidOtaPayload,
+    /// U-Boot legacy image (uImage) header binary kind, using the image name
+    /// as version or, when unset, the image creation date.
+    UImageHeader,
+    /// x86 Linux Kernel binary kind, exclusively using the x86 boot protocol
+    /// header and not trying other architectures.
+    X86Kernel,
+    /// ARM (32-bit) Linux Kernel binary kind, exclusively handling ARM zImage
+    /// and ARM uImage formats and not trying other architectures.
+    ArmKernel,
+    /// Package metadata (`package.json`, `Cargo.toml` or `setup.py`) embedded
+    /// in the binary.
+    ///
+    /// This is a heuristic and is prone to false positives.
+    PackageInfo,
+    /// UEFI capsule update binary kind.
+    EfiCapsule,
+    /// U-Boot script binary kind, using the `setenv bootversion` (or
+    /// `setenv version`) command, either as raw text or wrapped in a uImage.
+    UBootScript,
+    /// Linux Kernel binary kind, trying every supported sub-format (uImage, ARM
+    /// zImage, PowerPC ELF, MIPS vmlinuz, x86, AArch64 Image and RISC-V Image)
+    /// in turn until one provides a version, instead of only the first one
+    /// detected.
+    LinuxKernelAll,
+    /// Distribution release information (`/etc/os-release`) embedded in the
+    /// binary, using the `VERSION_ID` field. Images compressed as a whole with
+    /// gzip or XZ are transparently decompressed.
+    EmbeddedConfig,
+    /// Helm chart archive binary kind, using the chart version from the
+    /// top-level `Chart.yaml` or, when unset, the application version.
+    Helm,
+    /// Microcontroller firmware binary kind, using the `CMSIS-PACK version`
+    /// string. Firmware in Intel HEX or Motorola S-record format is converted
+    /// to the raw image first.
+    MicrocontrollerFirmware,
+    /// OpenWrt package (`.ipk`) binary kind, using the `Version` field of the
+    /// package control file.
+    OpenwrtPackage,
+    /// XZ compressed Linux Kernel binary kind (e.g: `vmlinuz` shipped as an XZ
+    /// compressed `vmlinux`), looking for the version in the decompressed
+    /// payload.
+    XzPayload,
+    /// Python wheel (`.whl`) binary kind, using the `Version` field of the
+    /// package `METADATA` file.
+    PythonWheel,
+    /// OCI/Docker container image binary kind, using the
+    /// `org.opencontainers.image.version` label (or a `version` key) of the
+    /// image configuration.
+    ContainerImage,
+    /// Rust binary kind, using the crate version string (e.g:
+    /// `env!("CARGO_PKG_VERSION")`) embedded in binaries built by rustc.
+    EmbeddedRust,
+    /// Barebox bootloader binary kind, using the version string following the
+    /// `barebox_version_string` symbol or, when stripped, the `barebox-<version>`
+    /// banner.
+    Barebox,
+    /// U-Boot Flattened Image Tree (FIT) binary kind, using the `version`
+    /// property of the kernel sub-image or, when unset, the U-Boot version
+    /// found in the sub-image descriptions.
+    #[allow(clippy::upper_case_acronyms)]
+    #[cfg_attr(feature = "serde", serde(rename = "fit"))]
+    FIT,
+    /// ELF binary kind, using the first version found in the toolchain
+    /// identification strings of the `.comment` section (e.g: `GCC: (GNU)
+    /// 12.3.0`).
+    ElfComment,
+    /// Android boot image (`boot.img`) binary kind, using the version of the
+    /// Linux Kernel it holds.
+    AndroidBoot,
+    /// Arm Trusted Firmware (TF-A) binary kind, using the version string of the
+    /// firmware images (e.g: BL2 or BL31), either standalone or bundled in a
+    /// Firmware Image Package (FIP).
+    ArmTrustedFirmware,
+    /// Go binary kind, using the Go toolchain version (e.g: `go1.21.5`) stored
+    /// in the build information of binaries built by Go 1.18 or newer.
+    GoBinary,
+    /// UEFI firmware management (FMP) capsule binary kind, using the firmware
+    /// version (`FwVersion`) of the first payload, as reported by the EFI
+    /// System Resource Table (e.g: `2.5`).
+    #[allow(clippy::upper_case_acronyms)]
+    #[cfg_attr(feature = "serde", serde(rename = "uefi-capsule"))]
+    UEFICapsule,
+    /// Linux Kernel module (`.ko`, optionally XZ compressed) binary kind, using
+    /// the kernel version of the `vermagic` module information (e.g:
+    /// `5.15.0-generic`).
+    KernelModule,
+    /// Busybox binary kind, using the version of its usage banner (e.g:
+    /// `BusyBox v1.36.1`).
+    Busybox,
+    /// OpenWrt sysupgrade image binary kind, using the `VERSION_CODE` field of
+    /// the image metadata (e.g: `r23497-6637af95aa`).
+    #[cfg_attr(feature = "serde", serde(rename = "openwrt"))]
+    OpenWrt,
+    /// Device Tree Blob (DTB) binary kind, using the `linux,version` property
+    /// of the root node or, when missing, its most specific `compatible` entry.
+    DeviceTree,
+    /// Raspberry Pi VideoCore firmware (`start.elf`) binary kind, using the
+    /// revision hash following its build date (e.g: `30f0c5e4d076`).
+    VideocoreFirmware,
+    /// U-Boot environment block binary kind, using the version held by its
+    /// `ver` variable, once its CRC32 checksum is verified.
+    UBootEnv,
+    /// GNU C Library (glibc) binary kind, using the version of its release
+    /// banner (e.g: `stable release version 2.36`).
+    #[cfg_attr(feature = "serde", serde(rename = "glibc"))]
+    GlibcLibrary,
+    /// musl libc binary kind, using the version of its banner (e.g: `musl libc
+    /// (x86_64) version 1.2.4`).
+    #[cfg_attr(feature = "serde", serde(rename = "musl"))]
+    MuslLibrary,
+    /// OpenSSL library (`libssl` and `libcrypto`) binary kind, using the version
+    /// of its version text (e.g: `OpenSSL 3.0.11`). BoringSSL is reported with
+    /// a `+BoringSSL` build metadata (e.g: `1.1.1+BoringSSL`).
+    #[cfg_attr(feature = "serde", serde(rename = "openssl"))]
+    OpenSSL,
+    /// Bare-metal ARM Cortex-M firmware binary kind, recognized by its vector
+    /// table, whose reset handler address is a Thumb one (least significant bit
+    /// set).
+    ///
+    /// Such firmware has no standard version string, so the whole image is
+    /// looked for the pattern set with
+    /// [`VersionFinderBuilder::custom_firmware_pattern`], or else for the
+    /// first `v` prefixed version (e.g: `v1.4.2`), which may as well belong to
+    /// a library linked into the firmware.
+    CortexMFirmware,
+    /// Raspberry Pi 4 bootloader EEPROM image (`pieeprom.upd`) binary kind, using
+    /// its release date (e.g: `2023/01/11`).
+    ///
+    /// The version is a `YYYY/MM/DD` date rather than a semantic version, so
+    /// versions are ordered by comparing them as strings.
+    #[cfg_attr(feature = "serde", serde(rename = "rpi4-eeprom"))]
+    RPi4Eeprom,
+    /// SquashFS filesystem image binary kind, using the format version and the
+    /// creation date of its superblock (e.g: `4.0 (built 2024-01-12)`).
+    ///
+    /// The format version is not the version of the `squashfs-tools` which built
+    /// the image, but tells apart the images built by its releases older than 4.0.
+    #[cfg_attr(feature = "serde", serde(rename = "squashfs"))]
+    SquashFS,
+    /// Windows Portable Executable (PE) binary kind, using the file version of
+    /// the `VS_FIXEDFILEINFO` structure of its version information resource
+    /// (e.g: `10.0.19041.1`).
+    #[cfg_attr(feature = "serde", serde(rename = "windows-pe"))]
+    WindowsPE,
+    /// Zephyr RTOS application binary kind, using the kernel version embedded
+    /// by Zephyr (e.g: `3.5.0`) or its exported `CONFIG_VERSION_STRING`.
+    #[cfg_attr(feature = "serde", serde(rename = "zephyr-rtos"))]
+    ZephyrRTOS,
+    /// Automatically detected binary kind, trying the known kinds in the
+    /// following priority order until one provides a version:
+    ///
+    /// 1. [`BinaryKind::UBoot`]
+    /// 2. [`BinaryKind::LinuxKernel`]
+    /// 3. [`BinaryKind::Busybox`]
+    ///
+    /// The reader is moved back to its start before each attempt.
+    Auto,
+    /// Custom binary kind, looking for the given regular expression as done
+    /// by [`version_with_pattern`].
+    Custom {
+        /// Name used to identify the custom kind.
+        name: String,
+        /// Regular expression whose first capture group is the version.
+        pattern: String,
+    },
+    /// Binary kind provided outside of the crate, whose version finders are
+    /// created by the given factory.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Other(Arc<dyn VersionFinderFactory>),
+}
+
+impl BinaryKind {
+    /// Create a [`BinaryKind::Custom`] binary kind.
+    pub fn custom(name: &str, pattern: &str) -> Self {
+        BinaryKind::Custom {
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+        }
+    }
+
+    /// Human-readable name of the binary kind (e.g: `"U-Boot bootloader"`).
+    pub const fn description(&self) -> &'static str {
+        match self {
+            BinaryKind::UBoot => "U-Boot bootloader",
+            BinaryKind::LinuxKernel => "Linux Kernel",
+            BinaryKind::AndroidOtaPayload => "Android OTA payload",
+            BinaryKind::UImageHeader => "U-Boot legacy image (uImage)",
+            BinaryKind::X86Kernel => "x86 Linux Kernel",
+            BinaryKind::ArmKernel => "ARM Linux Kernel",
+            BinaryKind::PackageInfo => "Embedded package metadata",
+            BinaryKind::EfiCapsule => "UEFI capsule update",
+            BinaryKind::UBootScript => "U-Boot script",
+            BinaryKind::LinuxKernelAll => "Linux Kernel (any format)",
+            BinaryKind::EmbeddedConfig => "Embedded distribution release information",
+            BinaryKind::Helm => "Helm chart",
+            BinaryKind::MicrocontrollerFirmware => "Microcontroller firmware",
+            BinaryKind::OpenwrtPackage => "OpenWrt package",
+            BinaryKind::XzPayload => "XZ compressed Linux Kernel",
+            BinaryKind::PythonWheel => "Python wheel",
+            BinaryKind::ContainerImage => "OCI/Docker container image",
+            BinaryKind::EmbeddedRust => "Rust binary",
+            BinaryKind::Barebox => "Barebox bootloader",
+            BinaryKind::FIT => "U-Boot Flattened Image Tree (FIT)",
+            BinaryKind::ElfComment => "ELF binary",
+            BinaryKind::AndroidBoot => "Android boot image",
+            BinaryKind::ArmTrustedFirmware => "Arm Trusted Firmware (TF-A)",
+            BinaryKind::GoBinary => "Go binary",
+            BinaryKind::UEFICapsule => "UEFI firmware management capsule",
+            BinaryKind::KernelModule => "Linux Kernel module",
+            BinaryKind::Busybox => "BusyBox",
+            BinaryKind::OpenWrt => "OpenWrt sysupgrade image",
+            BinaryKind::DeviceTree => "Device Tree Blob",
+            BinaryKind::VideocoreFirmware => "Raspberry Pi VideoCore firmware",
+            BinaryKind::UBootEnv => "U-Boot environment",
+            BinaryKind::GlibcLibrary => "GNU C Library",
+            BinaryKind::MuslLibrary => "musl libc",
+            BinaryKind::OpenSSL => "OpenSSL library",
+            BinaryKind::CortexMFirmware => "ARM Cortex-M firmware",
+            BinaryKind::RPi4Eeprom => "Raspberry Pi 4 bootloader EEPROM",
+            BinaryKind::SquashFS => "SquashFS filesystem",
+            BinaryKind::WindowsPE => "Windows PE binary",
+            BinaryKind::ZephyrRTOS => "Zephyr RTOS application",
+            BinaryKind::Auto => "Automatically detected binary",
+            BinaryKind::Custom { .. } => "Custom pattern",
+            BinaryKind::Other(_) => "Other binary",
+        }
+    }
+
+    /// Most common MIME type of the binary kind files, which is
+    /// `"application/octet-stream"` for the raw firmware images and for the
+    /// kinds not tied to a file format.
+    pub const fn mime_hint(&self) -> &'static str {
+        match self {
+            BinaryKind::EmbeddedRust
+            | BinaryKind::ElfComment
+            | BinaryKind::GoBinary
+            | BinaryKind::Busybox
+            | BinaryKind::VideocoreFirmware
+            | BinaryKind::ZephyrRTOS => "application/x-executable",
+            BinaryKind::KernelModule => "application/x-object",
+            BinaryKind::GlibcLibrary | BinaryKind::MuslLibrary | BinaryKind::OpenSSL => {
+                "application/x-sharedlib"
+            }
+            BinaryKind::Helm | BinaryKind::OpenwrtPackage => "application/gzip",
+            BinaryKind::PythonWheel => "application/zip",
+            BinaryKind::ContainerImage => "application/x-tar",
+            BinaryKind::XzPayload => "application/x-xz",
+            BinaryKind::SquashFS => "application/vnd.squashfs",
+            BinaryKind::WindowsPE => "application/vnd.microsoft.portable-executable",
+            _ => "application/octet-stream",
+        }
+    }
+
+    /// Iterate over every binary kind, in a stable order, except for
+    /// [`BinaryKind::Auto`] and [`BinaryKind::Custom`].
+    ///
+    /// ```no_run
+    /// # async fn f() -> Result<(), find_binary_version::FindVersionError> {
+    /// use find_binary_version::{version, BinaryKind};
+    /// use tokio::io::AsyncSeekExt;
+    ///
+    /// let mut file = tokio::io::BufReader::new(tokio::fs::File::open("firmware.bin").await?);
+    /// for kind in BinaryKind::iter() {
+    ///     file.rewind().await?;
+    ///     if let Some(v) = version(&mut file, kind.clone()).await? {
+    ///         println!("{:?}: {}", kind, v);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter() -> impl Iterator<Item = BinaryKind> {
+        // Kept in the declaration order, as checked by the tests
+        [
+            BinaryKind::UBoot,
+            BinaryKind::LinuxKernel,
+            BinaryKind::AndroidOtaPayload,
+            BinaryKind::UImageHeader,
+            BinaryKind::X86Kernel,
+            BinaryKind::ArmKernel,
+            BinaryKind::PackageInfo,
+            BinaryKind::EfiCapsule,
+            BinaryKind::UBootScript,
+            BinaryKind::LinuxKernelAll,
+            BinaryKind::EmbeddedConfig,
+            BinaryKind::Helm,
+            BinaryKind::MicrocontrollerFirmware,
+            BinaryKind::OpenwrtPackage,
+            BinaryKind::XzPayload,
+            BinaryKind::PythonWheel,
+            BinaryKind::ContainerImage,
+            BinaryKind::EmbeddedRust,
+            BinaryKind::Barebox,
+            BinaryKind::FIT,
+            BinaryKind::ElfComment,
+            BinaryKind::AndroidBoot,
+            BinaryKind::ArmTrustedFirmware,
+            BinaryKind::GoBinary,
+            BinaryKind::UEFICapsule,
+            BinaryKind::KernelModule,
+            BinaryKind::Busybox,
+            BinaryKind::OpenWrt,
+            BinaryKind::DeviceTree,
+            BinaryKind::VideocoreFirmware,
+            BinaryKind::UBootEnv,
+            BinaryKind::GlibcLibrary,
+            BinaryKind::MuslLibrary,
+            BinaryKind::OpenSSL,
+            BinaryKind::CortexMFirmware,
+            BinaryKind::RPi4Eeprom,
+            BinaryKind::SquashFS,
+            BinaryKind::WindowsPE,
+            BinaryKind::ZephyrRTOS,
+        ]
+        .into_iter()
+    }
+}
+
+/// Format the binary kind with the kebab-case name also used when it is
+/// serialized (e.g: `"u-boot"`), or the name of [`BinaryKind::Custom`].
+impl fmt::Display for BinaryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BinaryKind::UBoot => "u-boot",
+            BinaryKind::LinuxKernel => "linux-kernel",
+            BinaryKind::AndroidOtaPayload => "android-ota-payload",
+            BinaryKind::UImageHeader => "u-image-header",
+            BinaryKind::X86Kernel => "x86-kernel",
+            BinaryKind::ArmKernel => "arm-kernel",
+            BinaryKind::PackageInfo => "package-info",
+            BinaryKind::EfiCapsule => "efi-capsule",
+            BinaryKind::UBootScript => "u-boot-script",
+            BinaryKind::LinuxKernelAll => "linux-kernel-all",
+            BinaryKind::EmbeddedConfig => "embedded-config",
+            BinaryKind::Helm => "helm",
+            BinaryKind::MicrocontrollerFirmware => "microcontroller-firmware",
+            BinaryKind::OpenwrtPackage => "openwrt-package",
+            BinaryKind::XzPayload => "xz-payload",
+            BinaryKind::PythonWheel => "python-wheel",
+            BinaryKind::ContainerImage => "container-image",
+            BinaryKind::EmbeddedRust => "embedded-rust",
+            BinaryKind::Barebox => "barebox",
+            BinaryKind::FIT => "fit",
+            BinaryKind::ElfComment => "elf-comment",
+            BinaryKind::AndroidBoot => "android-boot",
+            BinaryKind::ArmTrustedFirmware => "arm-trusted-firmware",
+            BinaryKind::GoBinary => "go-binary",
+            BinaryKind::UEFICapsule => "uefi-capsule",
+            BinaryKind::KernelModule => "kernel-module",
+            BinaryKind::Busybox => "busybox",
+            BinaryKind::OpenWrt => "openwrt",
+            BinaryKind::DeviceTree => "device-tree",
+            BinaryKind::VideocoreFirmware => "videocore-firmware",
+            BinaryKind::UBootEnv => "u-boot-env",
+            BinaryKind::GlibcLibrary => "glibc",
+            BinaryKind::MuslLibrary => "musl",
+            BinaryKind::OpenSSL => "openssl",
+            BinaryKind::CortexMFirmware => "cortex-m-firmware",
+            BinaryKind::RPi4Eeprom => "rpi4-eeprom",
+            BinaryKind::SquashFS => "squashfs",
+            BinaryKind::WindowsPE => "windows-pe",
+            BinaryKind::ZephyrRTOS => "zephyr-rtos",
+            BinaryKind::Auto => "auto",
+            BinaryKind::Custom { name, .. } => name,
+            BinaryKind::Other(factory) => factory.name(),
+        })
+    }
+}
+
+/// Parse the binary kind from its name, as formatted by its `Display`
+/// implementation, ignoring the case.
+///
+/// [`BinaryKind::Custom`] needs a pattern, so it can not be parsed.
+///
+/// ```
+/// use find_binary_version::BinaryKind;
+///
+/// let kind: BinaryKind = "U-Boot".parse().unwrap();
+/// assert_eq!(kind.to_string(), "u-boot");
+/// assert!("bootloader".parse::<BinaryKind>().is_err());
+/// ```
+impl FromStr for BinaryKind {
+    type Err = UnknownBinaryKind;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BinaryKind::iter()
+            .chain(iter::once(BinaryKind::Auto))
+            .find(|kind| kind.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| UnknownBinaryKind(s.to_string()))
+    }
 }
 
+/// Finder of the version of a binary kind.
+///
+/// It is implemented with `#[async_trait::async_trait(?Send)]`, so the
+/// returned future is not required to be `Send`, and provided for other
+/// binary kinds through a [`VersionFinderFactory`]. The [`Custom`] and
+/// [`UBoot`] finders may be used directly by such implementations.
 #[async_trait::async_trait(?Send)]
-trait VersionFinder {
-    async fn get_version(&mut self) -> Option<String>;
+pub trait VersionFinder {
+    /// Get the version of the binary, or `Ok(None)` when the binary is not
+    /// of the kind or has no version information.
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError>;
 }
 
 /// Get the version for a specific binary.
+///
+/// `Ok(None)` is returned when the binary is not of the given kind or has no
+/// version information, while errors reading it are reported as `Err`.
 pub async fn version<R: AsyncRead + AsyncSeek + Unpin>(
+    buffer: &mut R,
+    kind: BinaryKind,
+) -> Result<Option<String>, FindVersionError> {
+    version_with_config(buffer, kind, Config::default()).await
+}
+
+async fn version_with_config<R: AsyncRead + AsyncSeek + Unpin>(
     mut buffer: &mut R,
     kind: BinaryKind,
-) -> Option<String> {
-    match kind {
-        BinaryKind::LinuxKernel => LinuxKernel::from_reader(&mut buffer).get_version().await,
-        BinaryKind::UBoot => UBoot::from_reader(&mut buffer).get_version().await,
+    config: Config<'_>,
+) -> Result<Option<String>, FindVersionError> {
+    #[cfg(feature = "tracing")]
+    let span = tracing::debug_span!("version", ?kind);
+
+    // BinaryKind::Auto applies the timeout to each kind it tries
+    let per_kind_timeout = matches!(kind, BinaryKind::Auto);
+    let find = async move {
+        match kind {
+            BinaryKind::LinuxKernel => {
+                LinuxKernel::from_reader(&mut buffer)
+                    .with_config(config)
+                    .get_version()
+                    .await
+            }
+            BinaryKind::UBoot => {
+                UBoot::from_reader(&mut buffer)
+                    .with_config(config)
+                    .get_version()
+                    .await
+            }
+            BinaryKind::AndroidOtaPayload => {
+                AndroidOtaPayload::from_reader(&mut buffer)
+                    .get_version()
+                    .await
+            }
+            BinaryKind::UImageHeader => UImage::from_reader(&mut buffer).get_version().await,
+            BinaryKind::X86Kernel => X86Kernel::from_reader(&mut buffer).get_version().await,
+            BinaryKind::ArmKernel => ArmKernel::from_reader(&mut buffer).get_version().await,
+            BinaryKind::PackageInfo => PackageInfo::from_reader(&mut buffer).get_version().await,
+            BinaryKind::EfiCapsule => EfiCapsule::from_reader(&mut buffer).get_version().await,
+            BinaryKind::UBootScript => UBootScript::from_reader(&mut buffer).get_version().await,
+            BinaryKind::LinuxKernelAll => {
+                LinuxKernelAll::from_reader(&mut buffer).get_version().await
+            }
+            BinaryKind::EmbeddedConfig => {
+                EmbeddedConfig::from_reader(&mut buffer).get_version().await
+            }
+            BinaryKind::Helm => Helm::from_reader(&mut buffer).get_version().await,
+            BinaryKind::MicrocontrollerFirmware => {
+                MicrocontrollerFirmware::from_reader(&mut buffer)
+                    .get_version()
+                    .await
+            }
+            BinaryKind::OpenwrtPackage => {
+                OpenwrtPackage::from_reader(&mut buffer).get_version().await
+            }
+            BinaryKind::XzPayload => XzPayload::from_reader(&mut buffer).get_version().await,
+            BinaryKind::PythonWheel => PythonWheel::from_reader(&mut buffer).get_version().await,
+            BinaryKind::ContainerImage => {
+                ContainerImage::from_reader(&mut buffer).get_version().await
+            }
+            BinaryKind::EmbeddedRust => EmbeddedRust::from_reader(&mut buffer).get_version().await,
+            BinaryKind::Barebox => {
+                Barebox::from_reader(&mut buffer)
+                    .with_config(config)
+                    .get_version()
+                    .await
+            }
+            BinaryKind::FIT => Fit::from_reader(&mut buffer).get_version().await,
+            BinaryKind::ElfComment => ElfComment::from_reader(&mut buffer).get_version().await,
+            BinaryKind::AndroidBoot => AndroidBoot::from_reader(&mut buffer).get_version().await,
+            BinaryKind::ArmTrustedFirmware => {
+                ArmTrustedFirmware::from_reader(&mut buffer)
+                    .get_version()
+                    .await
+            }
+            BinaryKind::GoBinary => GoBinary::from_reader(&mut buffer).get_version().await,
+            BinaryKind::UEFICapsule => UefiCapsule::from_reader(&mut buffer).get_version().await,
+            BinaryKind::KernelModule => KernelModule::from_reader(&mut buffer).get_version().await,
+            BinaryKind::Busybox => {
+                Busybox::from_reader(&mut buffer)
+                    .with_config(config)
+                    .get_version()
+                    .await
+            }
+            BinaryKind::OpenWrt => OpenWrt::from_reader(&mut buffer).get_version().await,
+            BinaryKind::DeviceTree => DeviceTree::from_reader(&mut buffer).get_version().await,
+            BinaryKind::VideocoreFirmware => {
+                VideocoreFirmware::from_reader(&mut buffer)
+                    .get_version()
+                    .await
+            }
+            BinaryKind::UBootEnv => UBootEnv::from_reader(&mut buffer).get_version().await,
+            BinaryKind::GlibcLibrary => {
+                GlibcLibrary::from_reader(&mut buffer)
+                    .with_config(config)
+                    .get_version()
+                    .await
+            }
+            BinaryKind::MuslLibrary => {
+                MuslLibrary::from_reader(&mut buffer)
+                    .with_config(config)
+                    .get_version()
+                    .await
+            }
+            BinaryKind::OpenSSL => {
+                OpenSSL::from_reader(&mut buffer)
+                    .with_config(config)
+                    .get_version()
+                    .await
+            }
+            BinaryKind::CortexMFirmware => {
+                CortexMFirmware::from_reader(&mut buffer)
+                    .with_config(config)
+                    .get_version()
+                    .await
+            }
+            BinaryKind::RPi4Eeprom => RPi4Eeprom::from_reader(&mut buffer).get_version().await,
+            BinaryKind::SquashFS => SquashFS::from_reader(&mut buffer).get_version().await,
+            BinaryKind::WindowsPE => WindowsPE::from_reader(&mut buffer).get_version().await,
+            BinaryKind::ZephyrRTOS => {
+                ZephyrRTOS::from_reader(&mut buffer)
+                    .with_config(config)
+                    .get_version()
+                    .await
+            }
+            BinaryKind::Auto => {
+                Auto::from_reader(&mut buffer)
+                    .with_config(config)
+                    .get_version()
+                    .await
+            }
+            BinaryKind::Custom { pattern, .. } => {
+                Custom::from_reader(&mut buffer, pattern.as_str())
+                    .with_config(config)
+                    .get_version()
+                    .await
+            }
+            BinaryKind::Other(factory) => factory.create(&mut *buffer).get_version().await,
+        }
+    };
+
+    #[cfg(feature = "tracing")]
+    let find = tracing::Instrument::instrument(find, span);
+
+    let version = if per_kind_timeout {
+        find.await
+    } else {
+        config.with_timeout(find).await
+    };
+    debug!(?version, "finished looking for the version");
+
+    error::eof_as_not_found(version)
+}
+
+/// Get the version for a specific binary, giving up once the timeout expires.
+///
+/// This guards against malformed binaries some finders may get stuck on (e.g:
+/// a compressed kernel never reaching its end), in which case `Ok(None)` is
+/// returned. For [`BinaryKind::Auto`] the timeout applies to each of the kinds
+/// it tries, so a kind timing out does not prevent the next ones from
+/// providing the version.
+///
+/// The reader may be left at any position after a timeout, so it must be
+/// moved back before being used again.
+pub async fn version_with_timeout<R: AsyncRead + AsyncSeek + Unpin>(
+    buffer: &mut R,
+    kind: BinaryKind,
+    timeout: Duration,
+) -> Result<Option<String>, FindVersionError> {
+    let config = Config {
+        timeout: Some(timeout),
+        ..Config::default()
+    };
+    version_with_config(buffer, kind, config).await
+}
+
+/// Get the version for a specific binary, along with the binary kind which
+/// provided it.
+///
+/// For [`BinaryKind::Auto`] the kind is the first one which provided a
+/// version, otherwise it is the given kind. The offset of the version is
+/// looked up in the binary once it is found.
+pub async fn version_info<R: AsyncRead + AsyncSeek + Unpin>(
+    buffer: &mut R,
+    kind: BinaryKind,
+) -> Result<Option<VersionInfo>, FindVersionError> {
+    let info = if let BinaryKind::Auto = kind {
+        error::eof_as_not_found(Auto::from_reader(buffer).get_version_info().await)?
+    } else {
+        version(buffer, kind.clone())
+            .await?
+            .map(|version| VersionInfo {
+                kind,
+                version,
+                offset: None,
+            })
+    };
+
+    match info {
+        Some(info) => Ok(Some(VersionInfo {
+            offset: version_info::find_offset(buffer, &info.version).await?,
+            ..info
+        })),
+        None => Ok(None),
     }
 }
 
+/// Get the version for the first of the given binary kinds providing one,
+/// trying all of them concurrently on clones of the reader.
+///
+/// The kinds are looked at concurrently in the same task, as the finders
+/// futures are not `Send`, so this helps when the reader waits on I/O. The
+/// kinds still running are cancelled once the result is known. When more
+/// than one kind provides a version, the first one in `kinds` wins, as well
+/// as its error if it fails.
+///
+/// Each clone must read the binary independently, which is the case of
+/// [`Cursor`](std::io::Cursor) but not of files, for which
+/// [`version_any_from_path`] should be used.
+pub async fn version_any<R: AsyncRead + AsyncSeek + Unpin + Clone>(
+    buffer: R,
+    kinds: &[BinaryKind],
+) -> Result<Option<VersionInfo>, FindVersionError> {
+    first_version_info(kinds.iter().cloned().map(|kind| {
+        let mut buffer = buffer.clone();
+        async move { version_info(&mut buffer, kind).await }
+    }))
+    .await
+}
+
+/// Get the version for the first of the given binary kinds providing one,
+/// opening the file once for each of them.
+///
+/// See [`version_any`] for how the kinds are tried.
+pub async fn version_any_from_path<P: AsRef<Path>>(
+    path: P,
+    kinds: &[BinaryKind],
+) -> Result<Option<VersionInfo>, FindVersionError> {
+    let path = path.as_ref();
+    first_version_info(kinds.iter().cloned().map(|kind| async move {
+        let mut buffer = BufReader::new(File::open(path).await?);
+        version_info(&mut buffer, kind).await
+    }))
+    .await
+}
+
+/// Get the version for each of the given binary kinds, trying them in order
+/// and keeping the result of every attempt, so it can be told which kinds
+/// were tried and failed before the one providing the version.
+///
+/// The reader is moved back to its start before each attempt. Kinds the
+/// binary is too short for provide no version, while other errors are
+/// returned.
+///
+/// ```no_run
+/// # async fn f() -> Result<(), find_binary_version::FindVersionError> {
+/// use find_binary_version::{version_with_fallback, BinaryKind};
+///
+/// let mut file = tokio::fs::File::open("boot.img").await?;
+/// let kinds = [BinaryKind::UBoot, BinaryKind::LinuxKernel];
+/// for (kind, version) in version_with_fallback(&mut file, &kinds).await? {
+///     println!("{}: {:?}", kind, version);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn version_with_fallback<R: AsyncRead + AsyncSeek + Unpin>(
+    buffer: &mut R,
+    kinds: &[BinaryKind],
+) -> Result<Vec<(BinaryKind, Option<String>)>, FindVersionError> {
+    let mut attempts = Vec::with_capacity(kinds.len());
+    for kind in kinds {
+        buffer.seek(SeekFrom::Start(0)).await?;
+        let version = error::eof_as_not_found(version(buffer, kind.clone()).await)?;
+        debug!("tried {} binary kind, found version {:?}", kind, version);
+        attempts.push((kind.clone(), version));
+    }
+
+    Ok(attempts)
+}
+
+/// Poll the futures concurrently, returning the result of the first one, in
+/// the given order, which is not `Ok(None)`.
+async fn first_version_info<F>(
+    finders: impl IntoIterator<Item = F>,
+) -> Result<Option<VersionInfo>, FindVersionError>
+where
+    F: Future<Output = Result<Option<VersionInfo>, FindVersionError>>,
+{
+    let mut pending: FuturesUnordered<_> = finders
+        .into_iter()
+        .enumerate()
+        .map(|(n, finder)| async move { (n, finder.await) })
+        .collect();
+    let mut results = Vec::new();
+    results.resize_with(pending.len(), || None);
+    let mut next = 0;
+
+    while let Some((n, result)) = pending.next().await {
+        results[n] = Some(result);
+
+        // Only the results in order are final, as an earlier kind may still
+        // provide a version
+        while let Some(result) = results.get_mut(next).and_then(Option::take) {
+            match result {
+                Ok(None) => next += 1,
+                result => return result,
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Detect the binary kind from the file header and get its version.
+///
+/// Binaries whose kind is not detected by [`detect_kind`] (e.g: U-Boot) are
+/// handled as [`BinaryKind::Auto`].
+pub async fn detect<R: AsyncRead + AsyncSeek + Unpin>(
+    buffer: &mut R,
+) -> Result<Option<VersionInfo>, FindVersionError> {
+    let kind = detect_kind(buffer).await?.unwrap_or(BinaryKind::Auto);
+    version_info(buffer, kind).await
+}
+
+/// Detect the binary kind, only looking at the file header.
+///
+/// The magic numbers of the supported formats are checked in turn, and the
+/// first one matching is returned. Linux kernels are reported as
+/// [`BinaryKind::LinuxKernel`], whatever their architecture. Formats without
+/// a magic number of their own (e.g: U-Boot or gzip compressed archives) are
+/// not detected. The reader is left at the begin of the file.
+pub async fn detect_kind<R: AsyncRead + AsyncSeek + Unpin>(
+    buffer: &mut R,
+) -> Result<Option<BinaryKind>, FindVersionError> {
+    Ok(detect::detect_kind(buffer).await?)
+}
+
+/// Detect the CPU architecture of a Linux kernel binary.
+///
+/// The architecture is read from the header of the kernel format: the
+/// `ih_arch` field for uImages and the boot protocol header for x86 kernels,
+/// while the other formats are specific to an architecture. `Ok(None)` is
+/// returned when the binary is not a supported Linux kernel.
+pub async fn detect_arch<R: AsyncRead + AsyncSeek + Unpin>(
+    buffer: &mut R,
+) -> Result<Option<KernelArch>, FindVersionError> {
+    error::eof_as_not_found(linuxkernel::detect_arch(buffer).await.map_err(Into::into))
+}
+
+/// Get the Android version of an Android boot image (`boot.img`), from the
+/// `os_version` field of its header (e.g: `11.0.0`).
+///
+/// `Ok(None)` is returned when the binary is not a boot image or the field
+/// is unset.
+pub async fn android_boot_os_version<R: AsyncRead + AsyncSeek + Unpin>(
+    buffer: &mut R,
+) -> Result<Option<String>, FindVersionError> {
+    error::eof_as_not_found(android::read_os_version(buffer).await.map_err(Into::into))
+}
+
+/// Get the version of a U-Boot binary, along with its variant.
+///
+/// Split builds provide both an SPL and a main U-Boot image, which may have
+/// different versions, so the variant tells which one was found.
+///
+/// The variant is the one of the banner, unless it has no SPL marker as done
+/// by older SPLs. The binary is then an SPL when it is not packaged as a
+/// uImage or a FIT image and is small enough (up to 256 KiB), which may
+/// require reading it up to that size.
+pub async fn version_uboot_variant<R: AsyncRead + Unpin>(
+    buffer: &mut R,
+) -> Result<Option<(String, UBootVariant)>, FindVersionError> {
+    UBoot::from_reader(buffer).get_version_variant().await
+}
+
+/// Get the version of a redundant U-Boot environment block, as done by
+/// [`BinaryKind::UBootEnv`].
+///
+/// Redundant environments hold two copies of the same size, each with a flags
+/// counter following its checksum. The valid copy written last is used.
+pub async fn version_uboot_env_redundant<R: AsyncRead + Unpin>(
+    buffer: &mut R,
+) -> Result<Option<String>, FindVersionError> {
+    UBootEnv::from_reader(buffer)
+        .redundant()
+        .get_version()
+        .await
+}
+
+/// Get the decoded U-Boot legacy image (uImage) header.
+///
+/// The header magic number and CRC32 checksum are validated.
+pub async fn uimage_header<R: AsyncRead + AsyncSeek + Unpin>(
+    buffer: &mut R,
+) -> Result<Option<UImageHeader>, FindVersionError> {
+    error::eof_as_not_found(uimage::read_uimage_header(buffer).await.map_err(Into::into))
+}
+
+/// Get the versions of an OpenWrt firmware image (e.g: a sysupgrade image),
+/// combining the build revision found by [`BinaryKind::OpenWrt`] with the
+/// kernel version found by [`BinaryKind::LinuxKernel`].
+///
+/// Either version is unset when it is not found, and `None` is returned when
+/// neither is.
+pub async fn openwrt_version<R: AsyncRead + AsyncSeek + Unpin>(
+    buffer: &mut R,
+) -> Result<Option<OpenWrtVersion>, FindVersionError> {
+    buffer.seek(SeekFrom::Start(0)).await?;
+    let distro = version(buffer, BinaryKind::OpenWrt).await?;
+    buffer.seek(SeekFrom::Start(0)).await?;
+    let kernel = version(buffer, BinaryKind::LinuxKernel).await?;
+
+    if distro.is_none() && kernel.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(OpenWrtVersion { distro, kernel }))
+}
+
+/// Get the version for a specific binary, only looking inside the given ELF
+/// sections.
+///
+/// The sections are tried in the given order, so the first one to provide a
+/// version wins. Non-ELF binaries, or binaries without any of the sections,
+/// have no version. Only the section header table and the sections looked at
+/// are read, seeking to each of them, so sections which do not fit in the
+/// binary are skipped. XZ compressed ELF binaries (e.g: `.ko.xz` kernel
+/// modules) are transparently decompressed, in memory.
+pub async fn version_in_sections<R: AsyncRead + AsyncSeek + Unpin>(
+    buffer: &mut R,
+    kind: BinaryKind,
+    sections: &[&str],
+) -> Result<Option<String>, FindVersionError> {
+    // Section offsets are relative to the begin of the file
+    buffer.seek(SeekFrom::Start(0)).await?;
+    let mut magic = Vec::new();
+    (&mut *buffer)
+        .take(elf_utils::XZ_MAGIC.len() as u64)
+        .read_to_end(&mut magic)
+        .await?;
+    if magic != elf_utils::XZ_MAGIC {
+        return version_in_named_sections(buffer, kind, sections).await;
+    }
+
+    buffer.seek(SeekFrom::Start(0)).await?;
+    match elf_utils::read_elf_image(buffer).await? {
+        Some(data) => version_in_named_sections(&mut Cursor::new(data), kind, sections).await,
+        None => Ok(None),
+    }
+}
+
+/// Look for the version in the given sections of an uncompressed ELF binary,
+/// reading each of them on its own.
+async fn version_in_named_sections<R: AsyncRead + AsyncSeek + Unpin>(
+    buffer: &mut R,
+    kind: BinaryKind,
+    sections: &[&str],
+) -> Result<Option<String>, FindVersionError> {
+    let elf_sections = elf_utils::read_elf_sections(buffer).await?;
+    for name in sections {
+        for section in elf_sections.iter().filter(|s| s.name == *name) {
+            if section.offset.checked_add(section.size).is_none() {
+                continue;
+            }
+
+            let data = elf_utils::read_at(buffer, section.offset, section.size).await?;
+            if data.len() as u64 != section.size {
+                debug!("ELF section {} is past the end of the binary", section.name);
+                continue;
+            }
+            if let Some(version) = version(&mut Cursor::new(data), kind.clone()).await? {
+                return Ok(Some(version));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Get the version for a specific pattern, only looking inside the named ELF
+/// section.
+///
+/// Unlike [`version_in_sections`], only the ELF header, the section header
+/// table and the section itself are read, seeking to each of them. Non-ELF
+/// binaries, or binaries without the section, have no version. When many
+/// sections have the given name, the first one is used.
+///
+/// ```no_run
+/// # async fn f() -> Result<(), find_binary_version::FindVersionError> {
+/// use find_binary_version::version_in_elf_section;
+///
+/// let mut file = tokio::io::BufReader::new(tokio::fs::File::open("firmware.elf").await?);
+/// let version = version_in_elf_section(&mut file, ".rodata", r"firmware v(\S+)").await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn version_in_elf_section<R: AsyncRead + AsyncSeek + Unpin>(
+    buffer: &mut R,
+    section: &str,
+    pattern: &str,
+) -> Result<Option<String>, FindVersionError> {
+    let sections = elf_utils::read_elf_sections(buffer).await?;
+    let section = match sections.iter().find(|s| s.name == section) {
+        Some(section) => section,
+        None => return Ok(None),
+    };
+    debug!(
+        "looking for pattern in ELF section {} at offset {:#x}",
+        section.name, section.offset
+    );
+
+    buffer.seek(SeekFrom::Start(section.offset)).await?;
+    version_with_pattern(&mut buffer.take(section.size), pattern).await
+}
+
+/// Get the version for a specific binary, verifying its integrity first.
+///
+/// The whole binary is read to compute its checksum, so it is read twice
+/// when the check succeeds.
+pub async fn version_with_integrity_check<R: AsyncRead + AsyncSeek + Unpin>(
+    buffer: &mut R,
+    kind: BinaryKind,
+    check: IntegrityCheck,
+) -> Result<Option<String>, FindVersionError> {
+    integrity::verify(buffer, check).await?;
+
+    version(buffer, kind).await
+}
+
+/// Check a pattern is a valid regular expression, so invalid patterns are
+/// reported before looking for them (e.g: when loading them from a
+/// configuration file).
+///
+/// ```
+/// use find_binary_version::verify_pattern;
+///
+/// assert!(verify_pattern(r"U-Boot (\S+)").is_ok());
+/// assert!(verify_pattern(r"U-Boot (\S+").is_err());
+/// ```
+pub fn verify_pattern(pattern: &str) -> Result<(), regex::Error> {
+    Regex::new(pattern).map(|_| ())
+}
+
 /// Get the version for a specific pattern.
 pub async fn version_with_pattern<R: AsyncRead + Unpin>(
+    buffer: &mut R,
+    pattern: &str,
+) -> Result<Option<String>, FindVersionError> {
+    version_with_compiled_pattern(buffer, &Regex::new(pattern)?).await
+}
+
+/// Get the version for a specific, already compiled, pattern.
+///
+/// This avoids compiling the same pattern again when looking for it in many
+/// binaries.
+pub async fn version_with_compiled_pattern<R: AsyncRead + Unpin>(
+    mut buffer: &mut R,
+    pattern: &Regex,
+) -> Result<Option<String>, FindVersionError> {
+    Custom::from_reader(&mut buffer, pattern)
+        .get_version()
+        .await
+}
+
+/// Get all the capture groups of the first match of a specific pattern.
+///
+/// The first group is the whole match, followed by the pattern groups, those
+/// which did not participate in the match being `None`. This allows patterns
+/// with more than one meaningful group, as [`version_with_pattern`] only
+/// returns the first one.
+///
+/// ```no_run
+/// # async fn f() -> Result<(), find_binary_version::FindVersionError> {
+/// use find_binary_version::version_captures;
+///
+/// let mut file = tokio::fs::File::open("firmware.bin").await?;
+/// if let Some(captures) = version_captures(&mut file, r"(\w+ \w+) version ([\d.]+)").await? {
+///     println!("{:?} {:?}", captures[1], captures[2]);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn version_captures<R: AsyncRead + Unpin>(
+    mut buffer: &mut R,
+    pattern: &str,
+) -> Result<Option<Vec<Option<String>>>, FindVersionError> {
+    Custom::from_reader(&mut buffer, pattern)
+        .get_captures()
+        .await
+}
+
+/// Get the versions of the first `n` distinct matches of a specific pattern,
+/// whose first capture group is the version.
+///
+/// The binary is read until `n` distinct versions are found, so the whole
+/// binary is only read when it has fewer of them. The versions are in the
+/// order they are found, and those found again are skipped unless enabled
+/// with [`VersionFinderBuilder::with_duplicates`].
+///
+/// ```no_run
+/// # async fn f() -> Result<(), find_binary_version::FindVersionError> {
+/// use find_binary_version::version_first_n;
+///
+/// let mut file = tokio::io::BufReader::new(tokio::fs::File::open("firmware.bin").await?);
+/// for version in version_first_n(&mut file, r"version (\d+\.\d+\.\d+)", 5).await? {
+///     println!("{}", version);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn version_first_n<R: AsyncRead + Unpin>(
     mut buffer: &mut R,
     pattern: &str,
-) -> Option<String> {
+    n: usize,
+) -> Result<Vec<String>, FindVersionError> {
     Custom::from_reader(&mut buffer, pattern)
+        .get_versions(n)
+        .await
+}
+
+/// Get the version for a specific pattern, using its `group_name` named
+/// capture group as version.
+///
+/// ```no_run
+/// # async fn f() -> Result<(), find_binary_version::FindVersionError> {
+/// use find_binary_version::version_with_named_group;
+///
+/// let mut file = tokio::io::BufReader::new(tokio::fs::File::open("firmware.bin").await?);
+/// let version = version_with_named_group(&mut file, r"version=(?P<ver>[0-9.]+)", "ver").await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Matches where the group does not participate provide no version, while a
+/// group missing from the pattern is reported as
+/// [`FindVersionError::InvalidPattern`].
+pub async fn version_with_named_group<R: AsyncRead + Unpin>(
+    mut buffer: &mut R,
+    pattern: &str,
+    group_name: &str,
+) -> Result<Option<String>, FindVersionError> {
+    Custom::from_reader(&mut buffer, pattern)
+        .with_group(group_name)
         .get_version()
         .await
 }
+
+/// Get the version for a specific binary, only looking at its first
+/// `max_bytes` bytes.
+///
+/// `Ok(None)` is returned when the version is not found within the limit,
+/// even if it is stored further in the binary, which avoids long scans of
+/// large images. This is a shortcut for [`VersionFinderBuilder::max_bytes`].
+pub async fn version_with_limit<R: AsyncRead + AsyncSeek + Unpin>(
+    buffer: &mut R,
+    kind: BinaryKind,
+    max_bytes: u64,
+) -> Result<Option<String>, FindVersionError> {
+    VersionFinderBuilder::new()
+        .max_bytes(max_bytes)
+        .build(kind)
+        .version(buffer)
+        .await
+}
+
+/// Get the version for a specific pattern, in a binary whose strings use the
+/// given text encoding (e.g: UTF-16 in some legacy firmware).
+///
+/// The binary is read in chunks, decoded to UTF-8 before looking for the
+/// pattern, which is matched against each run of non-control characters.
+/// UTF-16 strings are looked for at both even and odd offsets. The encoding
+/// is guessed by [`detect_encoding`] from the first chunk when given as
+/// [`Encoding::Unknown`].
+pub async fn version_with_encoding<R: AsyncRead + Unpin>(
+    buffer: &mut R,
+    pattern: &str,
+    encoding: Encoding,
+) -> Result<Option<String>, FindVersionError> {
+    let re = Regex::new(pattern)?;
+    encoding::find_version(buffer, &re, encoding).await
+}
+
+/// Get the version for a specific pattern, only looking at the first
+/// `max_bytes` bytes of the binary.
+pub async fn version_with_pattern_with_limit<R: AsyncRead + Unpin>(
+    buffer: &mut R,
+    pattern: &str,
+    max_bytes: u64,
+) -> Result<Option<String>, FindVersionError> {
+    version_with_pattern(&mut buffer.take(max_bytes), pattern).await
+}
+
+/// Get the version for a specific binary already loaded in memory.
+pub async fn version_from_bytes(
+    data: &[u8],
+    kind: BinaryKind,
+) -> Result<Option<String>, FindVersionError> {
+    version(&mut Cursor::new(data), kind).await
+}
+
+/// Get the version for a specific binary file.
+///
+/// Errors opening the file (e.g: not found or permission denied) are reported
+/// as [`FindVersionError::Io`].
+pub async fn version_from_path<P: AsRef<Path>>(
+    path: P,
+    kind: BinaryKind,
+) -> Result<Option<String>, FindVersionError> {
+    let mut buffer = BufReader::new(File::open(path).await?);
+    version(&mut buffer, kind).await
+}
+
+/// Get the version for each of the given binary files, looking at up to
+/// `concurrency` of them at the same time.
+///
+/// The files are yielded along with their version as soon as it is found, so
+/// in any order. Errors (e.g: a file not found) are reported for the file
+/// they happened with, the other files still being looked at.
+///
+/// The finders are not `Send` (see [`VersionFinder`]), so the files cannot be
+/// spawned as tasks bounded by a semaphore. They are instead looked at
+/// concurrently from the task polling the stream, which bounds them the same
+/// way but runs them on a single thread at a time. For parallelism across
+/// threads, the stream may be split among tasks, each one building its own
+/// batch.
+///
+/// ```no_run
+/// # async fn f() {
+/// use find_binary_version::{version_batch, BinaryKind};
+/// use futures_util::StreamExt;
+///
+/// let files = vec!["u-boot.img".into(), "u-boot-spl.bin".into()];
+/// let mut versions = version_batch(files, BinaryKind::UBoot, 4);
+/// while let Some((path, version)) = versions.next().await {
+///     println!("{}: {:?}", path.display(), version);
+/// }
+/// # }
+/// ```
+pub fn version_batch(
+    files: Vec<PathBuf>,
+    kind: BinaryKind,
+    concurrency: usize,
+) -> impl Stream<Item = (PathBuf, Result<Option<String>, FindVersionError>)> + Unpin {
+    stream::iter(files)
+        .map(move |path| {
+            let kind = kind.clone();
+            async move {
+                let version = version_from_path(&path, kind).await;
+                (path, version)
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+}
+
+/// Get the version for a specific binary stored in an archive (e.g: `.tar.gz`
+/// or `.zip`), at the given path inside of it.
+///
+/// The archive is streamed from its start and only the member is extracted,
+/// in memory, nothing being written to disk. Archives which are not
+/// compressed (e.g: ZIP, which requires seeking, or plain tar) are read in
+/// memory first. `Ok(None)` is returned when the reader is not an archive or
+/// the member is not found in it.
+///
+/// ```no_run
+/// # async fn f() -> Result<(), find_binary_version::FindVersionError> {
+/// use find_binary_version::{version_from_archive, BinaryKind};
+///
+/// let mut file = tokio::fs::File::open("firmware.tar.gz").await?;
+/// let version = version_from_archive(&mut file, "boot/u-boot.bin", BinaryKind::UBoot).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn version_from_archive<R: AsyncRead + AsyncSeek + Unpin>(
+    buffer: &mut R,
+    inner_path: &str,
+    kind: BinaryKind,
+) -> Result<Option<String>, FindVersionError> {
+    let mut magic = Vec::with_capacity(archive::MAGIC_SIZE);
+    buffer.seek(SeekFrom::Start(0)).await?;
+    (&mut *buffer)
+        .take(archive::MAGIC_SIZE as u64)
+        .read_to_end(&mut magic)
+        .await?;
+    buffer.seek(SeekFrom::Start(0)).await?;
+
+    // Some formats (e.g: ZIP) require seeking, which is not supported when
+    // streaming the archive, so it is read first
+    let data = if archive::is_streamable(&magic) {
+        archive::stream_archive_file(buffer, inner_path).await
+    } else {
+        let mut archive = Vec::new();
+        buffer.read_to_end(&mut archive).await?;
+        archive::read_archive_file(&archive, |f| f == inner_path)
+    };
+
+    match data {
+        Some(data) => version(&mut Cursor::new(data), kind).await,
+        None => Ok(None),
+    }
+}
+
+/// Get the version for a specific pattern from a binary file.
+///
+/// See [`version_from_path`] for how errors opening the file are reported.
+pub async fn version_with_pattern_from_path<P: AsRef<Path>>(
+    path: P,
+    pattern: &str,
+) -> Result<Option<String>, FindVersionError> {
+    let mut buffer = BufReader::new(File::open(path).await?);
+    version_with_pattern(&mut buffer, pattern).await
+}
+
+/// Get the version for a specific pattern from a binary already loaded in
+/// memory.
+pub async fn version_with_pattern_from_bytes(
+    data: &[u8],
+    pattern: &str,
+) -> Result<Option<String>, FindVersionError> {
+    version_with_pattern(&mut Cursor::new(data), pattern).await
+}
+
+/// Get the version stored at a known offset of the binary.
+///
+/// Up to `max_len` bytes are read from `offset`, and the first printable
+/// string found in them, ended by a NUL, a new line or any other non
+/// printable byte, is returned. This avoids scanning the whole binary for
+/// formats documenting where the version is stored.
+pub async fn version_at_offset<R: AsyncRead + AsyncSeek + Unpin>(
+    buffer: &mut R,
+    offset: u64,
+    max_len: usize,
+) -> Result<Option<String>, FindVersionError> {
+    buffer.seek(SeekFrom::Start(offset)).await?;
+    let mut window = Vec::new();
+    (&mut *buffer)
+        .take(max_len as u64)
+        .read_to_end(&mut window)
+        .await?;
+
+    Ok(Strings::with_config(&window[..], StringsConfig { min_length: 1 }).next())
+}
+
+/// Get every version found for a specific binary.
+///
+/// After a version is found, the search continues right after its first
+/// occurrence in the binary, so the matches do not overlap. The versions are
+/// deduplicated, keeping the order in which they are first found.
+///
+/// Only the binary kinds found by scanning the strings of the binary (U-Boot,
+/// Barebox, BusyBox, glibc, musl, OpenSSL, Zephyr and custom patterns) are
+/// looked at as they are read, providing every version. The other kinds need
+/// the whole binary and provide a single version.
+pub async fn version_all<R: AsyncRead + Unpin>(
+    buffer: &mut R,
+    kind: BinaryKind,
+) -> Result<Vec<String>, FindVersionError> {
+    iter_versions(buffer, kind).try_collect().await
+}
+
+/// Whether the version of the binary kind is found by scanning its strings,
+/// so it can be looked for in each chunk of the binary as it is read.
+fn is_scanned(kind: &BinaryKind) -> bool {
+    matches!(
+        kind,
+        BinaryKind::UBoot
+            | BinaryKind::Barebox
+            | BinaryKind::Busybox
+            | BinaryKind::GlibcLibrary
+            | BinaryKind::MuslLibrary
+            | BinaryKind::OpenSSL
+            | BinaryKind::ZephyrRTOS
+            | BinaryKind::Custom { .. }
+    )
+}
+
+/// State of the [`iter_versions`] stream.
+struct VersionsState<'a, R> {
+    buffer: &'a mut R,
+    kind: BinaryKind,
+    // Bytes read which are yet to be searched
+    data: Vec<u8>,
+    // Length of the start of the data which can be searched, being made of
+    // complete strings
+    end: usize,
+    eof: bool,
+    done: bool,
+    versions: Vec<String>,
+}
+
+impl<'a, R: AsyncRead + Unpin> VersionsState<'a, R> {
+    /// Read more of the binary, as a chunk for the scanned kinds or as a
+    /// whole for the others.
+    async fn fill(&mut self) -> Result<(), FindVersionError> {
+        if !is_scanned(&self.kind) {
+            self.buffer.read_to_end(&mut self.data).await?;
+            self.eof = true;
+            self.end = self.data.len();
+            return Ok(());
+        }
+
+        let len = self.data.len();
+        self.data.resize(len + custom::CHUNK_SIZE, 0);
+        let n = self.buffer.read(&mut self.data[len..]).await?;
+        self.data.truncate(len + n);
+        self.eof = n == 0;
+        self.end = custom::complete_strings_len(&self.data, self.eof, custom::CHUNK_SIZE);
+        Ok(())
+    }
+
+    /// Look for the next version, searching what is read of the binary
+    /// until there is nothing left.
+    async fn next_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        loop {
+            if self.end == 0 {
+                if self.eof {
+                    return Ok(None);
+                }
+                self.fill().await?;
+                continue;
+            }
+
+            let window = &self.data[..self.end];
+            let version = version(&mut Cursor::new(window), self.kind.clone()).await?;
+
+            // The search can only continue in the searched bytes when the
+            // version is found as is, otherwise the next ones are read
+            let position = version.as_ref().and_then(|version| {
+                window
+                    .windows(version.len())
+                    .position(|w| w == version.as_bytes())
+                    .filter(|_| !version.is_empty())
+                    .map(|position| position + version.len())
+            });
+            let consumed = position.unwrap_or(self.end);
+            self.data.drain(..consumed);
+            self.end -= consumed;
+            if !is_scanned(&self.kind) {
+                self.end = 0;
+                self.eof = true;
+            }
+
+            if let Some(version) = version {
+                if !self.versions.contains(&version) {
+                    self.versions.push(version.clone());
+                    return Ok(Some(version));
+                }
+            }
+        }
+    }
+}
+
+/// Get a stream of every version found for a specific binary, yielding each
+/// of them as soon as it is found.
+///
+/// The versions are the same as the ones of [`version_all`], and the stream
+/// ends after the first error. The binary is read in chunks as the stream is
+/// polled, so dropping it at any point leaves no work behind and it may be
+/// polled alongside other streams and cancelled.
+///
+/// ```no_run
+/// # async fn f() -> Result<(), find_binary_version::FindVersionError> {
+/// use find_binary_version::{iter_versions, BinaryKind};
+/// use futures_util::{pin_mut, TryStreamExt};
+///
+/// let mut file = tokio::io::BufReader::new(tokio::fs::File::open("u-boot.bin").await?);
+/// let versions = iter_versions(&mut file, BinaryKind::UBoot);
+/// pin_mut!(versions);
+/// while let Some(version) = versions.try_next().await? {
+///     println!("{}", version);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn iter_versions<R: AsyncRead + Unpin>(
+    buffer: &mut R,
+    kind: BinaryKind,
+) -> impl Stream<Item = Result<String, FindVersionError>> + '_ {
+    let state = VersionsState {
+        buffer,
+        kind,
+        data: Vec::new(),
+        end: 0,
+        eof: false,
+        done: false,
+        versions: Vec::new(),
+    };
+
+    stream::unfold(state, |mut state| async move {
+        if state.done {
+            return None;
+        }
+
+        match state.next_version().await {
+            Ok(version) => version.map(|version| (Ok(version), state)),
+            Err(e) => {
+                state.done = true;
+                Some((Err(e), state))
+            }
+        }
+    })
+}
+
+/// Get every version found for a specific pattern.
+///
+/// See [`version_all`] for how the matches are collected.
+pub async fn version_all_with_pattern<R: AsyncRead + Unpin>(
+    buffer: &mut R,
+    pattern: &str,
+) -> Result<Vec<String>, FindVersionError> {
+    version_all(buffer, BinaryKind::custom("pattern", pattern)).await
+}
@@ -2,55 +2,212 @@
 //
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::VersionFinder;
+use crate::{
+    arm_kernel, builder::Config, custom::Custom, elf_utils::ELF_MAGIC, uimage::UIMAGE_MAGIC_NUMBER,
+    x86kernel, FindVersionError, VersionFinder,
+};
 use regex::bytes::Regex;
-use std::{io::SeekFrom, str};
-use tokio::io::{self, AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+use std::{
+    io::{self, SeekFrom},
+    str,
+};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
 
 #[allow(clippy::enum_variant_names, clippy::upper_case_acronyms)]
-enum LinuxKernelKind {
+#[derive(Debug)]
+pub(crate) enum LinuxKernelKind {
     ARMzImage,
     UImage,
     X86bzImage,
     X86zImage,
+    AArch64Image,
+    RISCVImage,
+    PowerPC,
+    MIPS,
 }
 
-// U-Boot Image Magic Number
-const UIMAGE_MAGIC_NUMBER: u32 = 0x2705_1956;
+/// CPU architecture of a Linux kernel binary.
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KernelArch {
+    /// 32-bit x86.
+    X86,
+    /// 64-bit x86.
+    X86_64,
+    /// 32-bit ARM.
+    ARM,
+    /// 64-bit ARM.
+    AArch64,
+    /// RISC-V.
+    RISC_V,
+    /// MIPS, either 32-bit or 64-bit.
+    MIPS,
+    /// PowerPC.
+    PowerPC,
+    /// Architecture not covered by the other variants.
+    Unknown,
+}
+
+// U-Boot Image CPU architectures (ih_arch)
+pub(crate) const IH_ARCH_ARM: u8 = 2;
+const IH_ARCH_I386: u8 = 3;
+const IH_ARCH_MIPS: u8 = 5;
+const IH_ARCH_MIPS64: u8 = 6;
+const IH_ARCH_PPC: u8 = 7;
+const IH_ARCH_ARM64: u8 = 22;
+const IH_ARCH_X86_64: u8 = 24;
+const IH_ARCH_RISCV: u8 = 26;
+
+// ELF machine types (e_machine) of MIPS and PowerPC
+const EM_MIPS: u16 = 0x08;
+const EM_PPC: u16 = 0x14;
+const EM_PPC64: u16 = 0x15;
 
 // zImage Magic Number used in ARM
-const ARM_ZIMAGE_MAGIC_NUMBER: u32 = 0x016F_2818;
+pub(crate) const ARM_ZIMAGE_MAGIC_NUMBER: u32 = 0x016F_2818;
 
-async fn discover_linux_kernel_kind<R: AsyncRead + AsyncSeek + Unpin>(
-    buf: &mut R,
-) -> Option<LinuxKernelKind> {
+// Image Magic Number used in AArch64 ("ARM\x64")
+pub(crate) const AARCH64_IMAGE_MAGIC_NUMBER: u32 = 0x644D_5241;
+
+// Image magic2 Number used in RISC-V ("RSC\x05")
+pub(crate) const RISCV_IMAGE_MAGIC_NUMBER: u32 = 0x0543_5352;
+
+// AArch64 and RISC-V Image header size
+const IMAGE_HEADER_SIZE: u64 = 0x40;
+
+// Bytes kept between reads, so a version banner spanning two reads is found
+const IMAGE_OVERLAP: usize = 0x100;
+
+async fn is_uimage<R: AsyncRead + AsyncSeek + Unpin>(buf: &mut R) -> io::Result<bool> {
     // U-Boot Image Magic header is stored at begin of file
-    buf.seek(SeekFrom::Start(0x0000)).await.ok()?;
-    if buf.read_u32().await.ok()? == UIMAGE_MAGIC_NUMBER {
-        return Some(LinuxKernelKind::UImage);
-    }
+    let pos = buf.seek(SeekFrom::Start(0x0000)).await?;
+    trace!("seeked to offset {:#06x}", pos);
+    let magic = buf.read_u32().await?;
+    trace!("read {:#010x} at offset {:#06x}", magic, pos);
+    Ok(magic == UIMAGE_MAGIC_NUMBER)
+}
 
+async fn is_arm_zimage<R: AsyncRead + AsyncSeek + Unpin>(buf: &mut R) -> io::Result<bool> {
     // ARM zImage Magic header is stored at offset 0x0024 of file
-    buf.seek(SeekFrom::Start(0x0024)).await.ok()?;
-    if buf.read_u32_le().await.ok()? == ARM_ZIMAGE_MAGIC_NUMBER {
-        return Some(LinuxKernelKind::ARMzImage);
-    }
+    let pos = buf.seek(SeekFrom::Start(0x0024)).await?;
+    trace!("seeked to offset {:#06x}", pos);
+    let magic = buf.read_u32_le().await?;
+    trace!("read {:#010x} at offset {:#06x}", magic, pos);
+    Ok(magic == ARM_ZIMAGE_MAGIC_NUMBER)
+}
 
-    // Taken from: https://git.kernel.org/pub/scm/linux/kernel/git/torvalds/linux.git/tree/Documentation/x86/boot.txt#n144
+async fn is_aarch64_image<R: AsyncRead + AsyncSeek + Unpin>(buf: &mut R) -> io::Result<bool> {
+    // Taken from: https://www.kernel.org/doc/Documentation/arm64/booting.txt
     //
-    // Offset  Proto   Name            Meaning
-    // /Size
-    // ...
-    // 01FE/2  ALL     boot_flag       0xAA55 magic number
+    // Offset  Size  Name         Meaning
+    // 0000    4     code0        Executable code
+    // 0004    4     code1        Executable code
+    // 0008    8     text_offset  Image load offset, little endian
+    // 0010    8     image_size   Effective Image size, little endian
+    // 0018    8     flags        kernel flags, little endian
+    // 0020    8     res2         reserved
+    // 0028    8     res3         reserved
+    // 0030    8     res4         reserved
+    // 0038    4     magic        Magic number, little endian, "ARM\x64"
+    // 003C    4     res5         reserved (used for PE COFF offset)
+    let pos = buf.seek(SeekFrom::Start(0x0038)).await?;
+    trace!("seeked to offset {:#06x}", pos);
+    let magic = buf.read_u32_le().await?;
+    trace!("read {:#010x} at offset {:#06x}", magic, pos);
+    Ok(magic == AARCH64_IMAGE_MAGIC_NUMBER)
+}
+
+async fn is_riscv_image<R: AsyncRead + AsyncSeek + Unpin>(buf: &mut R) -> io::Result<bool> {
+    // Taken from: https://www.kernel.org/doc/Documentation/riscv/boot-image-header.rst
+    //
+    // Offset  Size  Name         Meaning
+    // 0000    4     code0        Executable code
+    // 0004    4     code1        Executable code
+    // 0008    8     text_offset  Image load offset, little endian
+    // 0010    8     image_size   Effective Image size, little endian
+    // 0018    8     flags        kernel flags, little endian
+    // 0020    4     version      Version of this header
+    // 0024    4     res1         reserved
+    // 0028    8     res2         reserved
+    // 0030    8     magic        Magic number, little endian, "RISCV\0\0\0" (deprecated)
+    // 0038    4     magic2       Magic number 2, little endian, "RSC\x05"
+    // 003C    4     res3         reserved (used for PE COFF offset)
+    let pos = buf.seek(SeekFrom::Start(0x0038)).await?;
+    trace!("seeked to offset {:#06x}", pos);
+    let magic = buf.read_u32_le().await?;
+    trace!("read {:#010x} at offset {:#06x}", magic, pos);
+    Ok(magic == RISCV_IMAGE_MAGIC_NUMBER)
+}
+
+/// Read the machine type (e_machine) of an ELF binary.
+async fn elf_machine<R: AsyncRead + AsyncSeek + Unpin>(buf: &mut R) -> io::Result<Option<u16>> {
+    // Offset  Size  Name       Meaning
+    // 0000    4     magic      "\x7fELF"
+    // 0004    1     ei_class   1 for 32-bit and 2 for 64-bit objects
+    // 0005    1     ei_data    1 for little-endian and 2 for big-endian objects
     // ...
-    // 0211/1	2.00+	loadflags	Boot protocol option flags
+    // 0012    2     e_machine  Target instruction set architecture
+    let pos = buf.seek(SeekFrom::Start(0x0000)).await?;
+    trace!("seeked to offset {:#06x}", pos);
+    let mut header = [0; 0x14];
+    buf.read_exact(&mut header).await?;
+    trace!("read {:02x?} at offset {:#06x}", header, pos);
+    if !header.starts_with(ELF_MAGIC) {
+        return Ok(None);
+    }
+
+    let machine = [header[0x12], header[0x13]];
+    let machine = match header[0x05] {
+        1 => u16::from_le_bytes(machine),
+        2 => u16::from_be_bytes(machine),
+        _ => return Ok(None),
+    };
+    Ok(Some(machine))
+}
+
+async fn is_powerpc_elf<R: AsyncRead + AsyncSeek + Unpin>(buf: &mut R) -> io::Result<bool> {
+    Ok(matches!(elf_machine(buf).await?, Some(EM_PPC | EM_PPC64)))
+}
+
+async fn is_mips_elf<R: AsyncRead + AsyncSeek + Unpin>(buf: &mut R) -> io::Result<bool> {
+    Ok(elf_machine(buf).await? == Some(EM_MIPS))
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(buf)))]
+pub(crate) async fn discover_linux_kernel_kind<R: AsyncRead + AsyncSeek + Unpin>(
+    buf: &mut R,
+) -> io::Result<Option<LinuxKernelKind>> {
+    if is_uimage(buf).await? {
+        return Ok(Some(LinuxKernelKind::UImage));
+    }
+
+    if is_arm_zimage(buf).await? {
+        return Ok(Some(LinuxKernelKind::ARMzImage));
+    }
+
+    if is_powerpc_elf(buf).await? {
+        return Ok(Some(LinuxKernelKind::PowerPC));
+    }
+
+    if is_mips_elf(buf).await? {
+        return Ok(Some(LinuxKernelKind::MIPS));
+    }
 
     // Verify the boot_flag magic number
-    buf.seek(SeekFrom::Start(0x01FE)).await.ok()?;
-    if buf.read_u16_le().await.ok()? != 0xAA55 {
-        return None;
+    if !x86kernel::has_boot_flag(buf).await? {
+        if is_aarch64_image(buf).await? {
+            return Ok(Some(LinuxKernelKind::AArch64Image));
+        }
+
+        if is_riscv_image(buf).await? {
+            return Ok(Some(LinuxKernelKind::RISCVImage));
+        }
+
+        return Ok(None);
     }
 
+    // Taken from: https://git.kernel.org/pub/scm/linux/kernel/git/torvalds/linux.git/tree/Documentation/x86/boot.txt#n144
+    //
     // Field name:	loadflags
     // Type:		modify (obligatory)
     // Offset/size:	0x211/1
@@ -62,145 +219,238 @@ async fn discover_linux_kernel_kind<R: AsyncRead + AsyncSeek + Unpin>(
     //         - If 0, the protected-mode code is loaded at 0x10000.
     //         - If 1, the protected-mode code is loaded at 0x100000.
     //   ...
-    buf.seek(SeekFrom::Start(0x0211)).await.ok()?;
-    match buf.read_u8().await.ok()? & 0x1 {
-        0 => Some(LinuxKernelKind::X86zImage),
-        1 => Some(LinuxKernelKind::X86bzImage),
-        _ => None,
+    let pos = buf.seek(SeekFrom::Start(0x0211)).await?;
+    trace!("seeked to offset {:#06x}", pos);
+    let loadflags = buf.read_u8().await?;
+    trace!("read {:#04x} at offset {:#06x}", loadflags, pos);
+    match loadflags & 0x1 {
+        0 => Ok(Some(LinuxKernelKind::X86zImage)),
+        1 => Ok(Some(LinuxKernelKind::X86bzImage)),
+        _ => Ok(None),
+    }
+}
+
+/// Detect the CPU architecture of the Linux kernel, from the header of the
+/// detected kernel kind.
+pub(crate) async fn detect_arch<R: AsyncRead + AsyncSeek + Unpin>(
+    buf: &mut R,
+) -> io::Result<Option<KernelArch>> {
+    let kind = discover_linux_kernel_kind(buf).await?;
+    debug!("discovered Linux kernel kind {:?}", kind);
+
+    Ok(match kind {
+        Some(LinuxKernelKind::UImage) => {
+            // ih_arch is stored at offset 0x001D of the uImage header
+            buf.seek(SeekFrom::Start(0x001D)).await?;
+            let arch = buf.read_u8().await?;
+            debug!("read uImage architecture {} at offset 0x001D", arch);
+            Some(match arch {
+                IH_ARCH_ARM => KernelArch::ARM,
+                IH_ARCH_I386 => KernelArch::X86,
+                IH_ARCH_MIPS | IH_ARCH_MIPS64 => KernelArch::MIPS,
+                IH_ARCH_PPC => KernelArch::PowerPC,
+                IH_ARCH_ARM64 => KernelArch::AArch64,
+                IH_ARCH_X86_64 => KernelArch::X86_64,
+                IH_ARCH_RISCV => KernelArch::RISC_V,
+                _ => KernelArch::Unknown,
+            })
+        }
+
+        Some(LinuxKernelKind::ARMzImage) => Some(KernelArch::ARM),
+
+        Some(LinuxKernelKind::X86bzImage | LinuxKernelKind::X86zImage) => {
+            if x86kernel::is_64bit(buf).await? {
+                Some(KernelArch::X86_64)
+            } else {
+                Some(KernelArch::X86)
+            }
+        }
+
+        Some(LinuxKernelKind::AArch64Image) => Some(KernelArch::AArch64),
+
+        Some(LinuxKernelKind::RISCVImage) => Some(KernelArch::RISC_V),
+
+        Some(LinuxKernelKind::PowerPC) => Some(KernelArch::PowerPC),
+
+        Some(LinuxKernelKind::MIPS) => Some(KernelArch::MIPS),
+
+        None => None,
+    })
+}
+
+pub(crate) async fn read_uimage_version<R: AsyncRead + AsyncSeek + Unpin>(
+    buf: &mut R,
+) -> io::Result<Option<String>> {
+    // Move to the begin of the file, so we can next read the
+    // buffer to match the version.
+    buf.seek(SeekFrom::Start(0)).await?;
+
+    // Read the Linux kernel version from the reader
+    let mut buffer = [0; 0x200];
+    let n = buf.read(&mut buffer).await?;
+    trace!("read {} bytes at offset 0x0000", n);
+
+    let re = Regex::new(r"(?P<version>\d+\.\d+[^\s\u{0}]*)").unwrap();
+    Ok(re
+        .captures(&buffer)
+        .and_then(|m| m.name("version"))
+        .and_then(|v| str::from_utf8(v.as_bytes()).ok())
+        .map(|v| v.to_string()))
+}
+
+/// Look for the Linux banner inside of an uncompressed kernel (AArch64 or
+/// RISC-V Image and PowerPC ELF), starting from the given offset.
+///
+/// The kernel headers do not point to the version string, but the kernel is
+/// not compressed, so the banner is looked for in the whole image.
+async fn read_image_version<R: AsyncRead + AsyncSeek + Unpin>(
+    buf: &mut R,
+    offset: u64,
+) -> io::Result<Option<String>> {
+    buf.seek(SeekFrom::Start(offset)).await?;
+
+    let re = Regex::new(r"Linux version (?P<version>[^\s\x00]+) ").unwrap();
+    let mut buffer = [0; 0x400];
+    let mut len = 0;
+    loop {
+        let n = buf.read(&mut buffer[len..]).await?;
+
+        // No more data to read
+        if n == 0 {
+            return Ok(None);
+        }
+        len += n;
+
+        if let Some(version) = re
+            .captures(&buffer[..len])
+            .and_then(|m| m.name("version"))
+            .and_then(|v| str::from_utf8(v.as_bytes()).ok())
+        {
+            debug!("matched the Image banner with version {}", version);
+            return Ok(Some(version.to_string()));
+        }
+
+        // Keep the tail of the buffer for the next read
+        if len > IMAGE_OVERLAP {
+            buffer.copy_within(len - IMAGE_OVERLAP..len, 0);
+            len = IMAGE_OVERLAP;
+        }
     }
 }
 
 pub(crate) struct LinuxKernel<'a, R: AsyncRead + AsyncSeek + Unpin> {
     buf: &'a mut R,
+    config: Config<'a>,
 }
 
 impl<'a, R: AsyncRead + AsyncSeek + Unpin> LinuxKernel<'a, R> {
     pub(crate) fn from_reader(buf: &'a mut R) -> Self {
-        LinuxKernel { buf }
+        LinuxKernel {
+            buf,
+            config: Config::default(),
+        }
+    }
+
+    pub(crate) fn with_config(self, config: Config<'a>) -> Self {
+        LinuxKernel { config, ..self }
     }
 }
 
 #[async_trait::async_trait(?Send)]
 impl<'a, R: AsyncRead + AsyncSeek + Unpin> VersionFinder for LinuxKernel<'a, R> {
-    async fn get_version(&mut self) -> Option<String> {
-        match discover_linux_kernel_kind(self.buf).await? {
-            LinuxKernelKind::ARMzImage => {
-                async fn get_version_from_arm<R: AsyncRead + Unpin>(mut rd: R) -> Option<String> {
-                    let mut buffer = Vec::default();
-                    compress_tools::tokio_support::uncompress_data(&mut rd, &mut buffer)
-                        .await
-                        .ok()?;
-                    let re = Regex::new(r"Linux version (?P<version>\S+).*").unwrap();
-                    re.captures(&buffer)
-                        .and_then(|m| m.name("version"))
-                        .and_then(|v| str::from_utf8(v.as_bytes()).ok())
-                        .map(|v| v.to_string())
-                }
-
-                let mut buffer = [0; 0x200];
-                loop {
-                    let n = self.buf.read(&mut buffer).await.ok()?;
-
-                    // No more data to read
-                    if n == 0 {
-                        return None;
-                    }
-
-                    // Look for compression format header
-                    for (offset, window) in buffer[0..n].windows(6).enumerate() {
-                        // Headers taken from:
-                        // https://github.com/torvalds/linux/blob/master/scripts/extract-vmlinux
-                        match window {
-                            [0x1f, 0x8b, 0x08, ..] => {}               // gzip
-                            [0xfd, b'7', b'z', b'X', b'Z', 0x00] => {} // xz
-                            [b'B', b'Z', b'h', ..] => {}               // bzip2
-                            [0x5d, 0x00, 0x00, ..] => {}               // lzma
-                            [0x89, 0x4c, 0x5a, ..] => {}               // lzo
-                            [0x02, b'!', b'L', 0x18, ..] => {}         // lz4
-                            [b'(', 0xb5, b'/', 0xfd, ..] => {}         // zstd
-                            _ => continue,
-                        }
-
-                        let mut slice = &buffer[offset..];
-                        let current = self.buf.seek(SeekFrom::Current(0)).await.ok()?;
-                        let rd = io::AsyncReadExt::chain(&mut slice, &mut self.buf);
-
-                        // Try to get version from uncompressed data
-                        if let Some(version) = get_version_from_arm(rd).await {
-                            return Some(version);
-                        }
-
-                        // Seek back to current position so we can keep looking
-                        // for the next compression header
-                        self.buf.seek(SeekFrom::Start(current)).await.ok()?;
-                    }
-                }
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        let kind = discover_linux_kernel_kind(self.buf).await?;
+        debug!("discovered Linux kernel kind {:?}", kind);
+        if let Some(pattern) = self.config.kernel_pattern {
+            if kind.is_none() {
+                return Ok(None);
             }
 
-            LinuxKernelKind::X86bzImage | LinuxKernelKind::X86zImage => {
-                // Taken from: https://git.kernel.org/pub/scm/linux/kernel/git/torvalds/linux.git/tree/Documentation/x86/boot.txt#n144
-                //
-                // Offset  Proto   Name            Meaning
-                // /Size
-                // ...
-                // 01F1/1  ALL(1   setup_sects     The size of the setup in sectors
-                // ...
-                // 020E/2  2.00+   kernel_version  Pointer to kernel version string
-
-                // Get the setup_sects information
-                self.buf.seek(SeekFrom::Start(0x01F1)).await.ok()?;
-                let setup_sects = u64::from(self.buf.read_u8().await.ok()?);
-
-                // Get kernel_version pointer
-                self.buf.seek(SeekFrom::Start(0x020E)).await.ok()?;
-                let kernel_version_ptr = u64::from(self.buf.read_u16_le().await.ok()?);
-
-                // Field name:     kernel_version
-                // Type:           read
-                // Offset/size:    0x20e/2
-                // Protocol:       2.00+
-                //
-                //   If set to a nonzero value, contains a pointer to a NUL-terminated
-                //   human-readable kernel version number string, less 0x200.  This can
-                //   be used to display the kernel version to the user.  This value
-                //   should be less than (0x200*setup_sects).
-                if kernel_version_ptr >= setup_sects * 0x200 {
-                    return None;
-                }
-
-                // Move to the kernel version location
-                self.buf
-                    .seek(SeekFrom::Start(kernel_version_ptr + 0x200))
-                    .await
-                    .ok()?;
+            self.buf.seek(SeekFrom::Start(0)).await?;
+            return Custom::from_reader(self.buf, pattern)
+                .with_config(self.config)
+                .get_version()
+                .await;
+        }
+
+        Ok(match kind {
+            Some(LinuxKernelKind::ARMzImage) => arm_kernel::read_zimage_version(self.buf).await?,
+
+            Some(LinuxKernelKind::X86bzImage | LinuxKernelKind::X86zImage) => {
+                x86kernel::read_version(self.buf).await?
+            }
+
+            Some(LinuxKernelKind::UImage) => read_uimage_version(self.buf).await?,
+
+            Some(LinuxKernelKind::AArch64Image | LinuxKernelKind::RISCVImage) => {
+                read_image_version(self.buf, IMAGE_HEADER_SIZE).await?
+            }
+
+            Some(LinuxKernelKind::PowerPC) => read_image_version(self.buf, 0).await?,
 
-                // Read the Linux kernel version from the reader
-                let mut buffer = [0; 0x200];
-                let _ = self.buf.read(&mut buffer).await.ok()?;
+            Some(LinuxKernelKind::MIPS) => {
+                // The compressed kernel is appended to the decompressor
+                self.buf.seek(SeekFrom::Start(0)).await?;
+                arm_kernel::read_zimage_version(self.buf).await?
+            }
+
+            None => None,
+        })
+    }
+}
+
+pub(crate) struct LinuxKernelAll<'a, R: AsyncRead + AsyncSeek + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> LinuxKernelAll<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        LinuxKernelAll { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> VersionFinder for LinuxKernelAll<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        // Unlike LinuxKernel, a sub-format whose magic number matches but has
+        // no version does not prevent the others from being tried.
+        if is_uimage(self.buf).await? {
+            if let Some(version) = read_uimage_version(self.buf).await? {
+                return Ok(Some(version));
+            }
+        }
 
-                let re = Regex::new(r"(?P<version>\d+.?\.[^\s\u{0}]+)").unwrap();
-                re.captures(&buffer)
-                    .and_then(|m| m.name("version"))
-                    .and_then(|v| str::from_utf8(v.as_bytes()).ok())
-                    .map(|v| v.to_string())
+        if is_arm_zimage(self.buf).await? {
+            if let Some(version) = arm_kernel::read_zimage_version(self.buf).await? {
+                return Ok(Some(version));
             }
+        }
 
-            LinuxKernelKind::UImage => {
-                // Move to the begin of the file, so we can next read the
-                // buffer to match the version.
-                self.buf.seek(SeekFrom::Start(0)).await.ok()?;
+        if is_powerpc_elf(self.buf).await? {
+            if let Some(version) = read_image_version(self.buf, 0).await? {
+                return Ok(Some(version));
+            }
+        }
 
-                // Read the Linux kernel version from the reader
-                let mut buffer = [0; 0x200];
-                let _ = self.buf.read(&mut buffer).await.ok()?;
+        if is_mips_elf(self.buf).await? {
+            self.buf.seek(SeekFrom::Start(0)).await?;
+            if let Some(version) = arm_kernel::read_zimage_version(self.buf).await? {
+                return Ok(Some(version));
+            }
+        }
 
-                let re = Regex::new(r"(?P<version>\d+.?\.[^\s\u{0}]+)").unwrap();
-                re.captures(&buffer)
-                    .and_then(|m| m.name("version"))
-                    .and_then(|v| str::from_utf8(v.as_bytes()).ok())
-                    .map(|v| v.to_string())
+        if x86kernel::has_boot_flag(self.buf).await? {
+            if let Some(version) = x86kernel::read_version(self.buf).await? {
+                return Ok(Some(version));
             }
         }
+
+        if is_aarch64_image(self.buf).await? || is_riscv_image(self.buf).await? {
+            return Ok(read_image_version(self.buf, IMAGE_HEADER_SIZE).await?);
+        }
+
+        Ok(None)
     }
 }
 
@@ -224,13 +474,97 @@ mod test {
         for (f, v) in &[
             ("arm-uImage", "4.1.15-1.2.0+g274a055"),
             ("arm-zImage", "4.4.1"),
+            ("arm64-Image", "5.10.0-9-arm64"),
+            ("riscv64-Image", "5.15.0-1004-generic"),
+            ("ppc-vmlinux.strip", "4.19.0-20-powerpc"),
+            ("ppc64le-vmlinux.strip", "5.10.0-9-powerpc64le"),
+            ("mips-vmlinuz", "4.19.0-20-4kc-malta"),
+            ("mipsel-vmlinuz", "5.10.0-9-4kc-malta"),
             ("x86-bzImage", "4.1.30-1-MANJARO"),
             ("x86-zImage", "4.1.30-1-MANJARO"),
         ] {
             assert_eq!(
-                version(&mut fixture(f).await, BinaryKind::LinuxKernel).await,
+                version(&mut fixture(f).await, BinaryKind::LinuxKernel)
+                    .await
+                    .unwrap(),
                 Some(v.to_string())
             );
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::LinuxKernelAll)
+                    .await
+                    .unwrap(),
+                Some(v.to_string())
+            );
+        }
+
+        for image in [
+            crate::helpers::make_linux_bzimage("6.1.0-13-amd64"),
+            crate::helpers::make_linux_uimage("6.1.0-13-amd64"),
+        ] {
+            assert_eq!(
+                version(&mut std::io::Cursor::new(image), BinaryKind::LinuxKernel)
+                    .await
+                    .unwrap(),
+                Some("6.1.0-13-amd64".to_string())
+            );
         }
     }
+
+    #[tokio::test]
+    async fn arch() {
+        use crate::{detect_arch, KernelArch};
+
+        for (f, a) in &[
+            ("arm-uImage", Some(KernelArch::ARM)),
+            ("arm-zImage", Some(KernelArch::ARM)),
+            ("arm64-Image", Some(KernelArch::AArch64)),
+            ("riscv64-Image", Some(KernelArch::RISC_V)),
+            ("ppc-vmlinux.strip", Some(KernelArch::PowerPC)),
+            ("ppc64le-vmlinux.strip", Some(KernelArch::PowerPC)),
+            ("mips-vmlinuz", Some(KernelArch::MIPS)),
+            ("mipsel-vmlinuz", Some(KernelArch::MIPS)),
+            ("x86-bzImage", Some(KernelArch::X86_64)),
+            ("x86-zImage", Some(KernelArch::X86_64)),
+        ] {
+            assert_eq!(detect_arch(&mut fixture(f).await).await.unwrap(), *a);
+        }
+
+        // A 32-bit kernel does not set XLF_KERNEL_64
+        let mut data = std::fs::read("tests/fixtures/linuxkernel/x86-bzImage").unwrap();
+        data[0x236] = 0;
+        assert_eq!(
+            detect_arch(&mut std::io::Cursor::new(&data)).await.unwrap(),
+            Some(KernelArch::X86)
+        );
+
+        // The uImage architecture is taken from the ih_arch field
+        let mut data = std::fs::read("tests/fixtures/linuxkernel/arm-uImage").unwrap();
+        data[0x1D] = 7;
+        assert_eq!(
+            detect_arch(&mut std::io::Cursor::new(&data)).await.unwrap(),
+            Some(KernelArch::PowerPC)
+        );
+
+        assert_eq!(
+            detect_arch(&mut std::io::Cursor::new(b"not a kernel"))
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn all_fallback() {
+        // A bogus uImage header in front of an x86 kernel
+        let mut data = std::fs::read("tests/fixtures/linuxkernel/x86-bzImage").unwrap();
+        data[..4].copy_from_slice(&0x2705_1956_u32.to_be_bytes());
+        data[4..0x40].fill(0);
+
+        assert_eq!(
+            version(&mut std::io::Cursor::new(&data), BinaryKind::LinuxKernelAll)
+                .await
+                .unwrap(),
+            Some("4.1.30-1-MANJARO".to_string())
+        );
+    }
 }
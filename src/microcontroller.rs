@@ -0,0 +1,167 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{strings::IntoStringsIter, FindVersionError, VersionFinder};
+use regex::Regex;
+use std::str;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+// Decode the hexadecimal pairs of a record into bytes.
+fn hex_bytes(s: &str) -> Option<Vec<u8>> {
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| match pair {
+            [_, _] => u8::from_str_radix(str::from_utf8(pair).ok()?, 16).ok(),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Decode the data records of an Intel HEX file.
+///
+/// The data is concatenated in the file order, as only the contents are
+/// needed to look for the version.
+fn decode_intel_hex(text: &str) -> Option<Vec<u8>> {
+    // Record layout: ':' LL AAAA TT DD... CC
+    let mut data = Vec::new();
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let record = hex_bytes(line.strip_prefix(':')?)?;
+        if record.len() < 5 || record.len() != usize::from(record[0]) + 5 {
+            return None;
+        }
+
+        // The sum of all the record bytes, including the checksum, is zero
+        if record.iter().fold(0u8, |acc, b| acc.wrapping_add(*b)) != 0 {
+            return None;
+        }
+
+        match record[3] {
+            // Data
+            0x00 => data.extend_from_slice(&record[4..record.len() - 1]),
+            // End Of File
+            0x01 => break,
+            // Extended addresses and start addresses carry no data
+            _ => {}
+        }
+    }
+
+    Some(data)
+}
+
+/// Decode the data records of a Motorola S-record file.
+///
+/// The data is concatenated in the file order, as only the contents are
+/// needed to look for the version.
+fn decode_srec(text: &str) -> Option<Vec<u8>> {
+    // Record layout: 'S' T CC AAAA... DD... KK
+    let mut data = Vec::new();
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let kind = line.strip_prefix('S')?.get(..1)?;
+        let record = hex_bytes(line.get(2..)?)?;
+        if record.len() < 2 || record.len() != usize::from(record[0]) + 1 {
+            return None;
+        }
+
+        // The checksum is the ones' complement of the sum of the other bytes
+        let (checksum, record) = record.split_last()?;
+        if !record.iter().fold(0u8, |acc, b| acc.wrapping_add(*b)) != *checksum {
+            return None;
+        }
+
+        // Data records using 16, 24 and 32-bit addresses
+        let address_len = match kind {
+            "1" => 2,
+            "2" => 3,
+            "3" => 4,
+            _ => continue,
+        };
+        data.extend_from_slice(record.get(1 + address_len..)?);
+    }
+
+    Some(data)
+}
+
+pub(crate) struct MicrocontrollerFirmware<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + Unpin> MicrocontrollerFirmware<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        MicrocontrollerFirmware { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for MicrocontrollerFirmware<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        // FIXME: Avoid reading the whole file
+        let mut buffer = Vec::new();
+        self.buf.read_to_end(&mut buffer).await?;
+
+        // Firmware distributed as text (Intel HEX or Motorola S-record) is
+        // converted to the raw image first.
+        let image = match str::from_utf8(&buffer) {
+            Ok(text) if text.starts_with(':') => decode_intel_hex(text),
+            Ok(text) if text.starts_with("S0") => decode_srec(text),
+            _ => None,
+        };
+
+        let re = Regex::new(r"CMSIS.PACK version (?P<version>[0-9]+\.[0-9]+\.[0-9]+)").unwrap();
+        for line in image.unwrap_or(buffer).into_strings_iter() {
+            if let Some(v) = re.captures(&line).and_then(|c| c.name("version")) {
+                return Ok(Some(v.as_str().to_string()));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("mcu/firmware.bin", Some("5.8.0")),
+            ("mcu/firmware.hex", Some("5.8.0")),
+            ("mcu/firmware.srec", Some("5.8.0")),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::MicrocontrollerFirmware)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+
+    #[test]
+    fn decode() {
+        let image = std::fs::read("tests/fixtures/mcu/firmware.bin").unwrap();
+        let hex = std::fs::read_to_string("tests/fixtures/mcu/firmware.hex").unwrap();
+        let srec = std::fs::read_to_string("tests/fixtures/mcu/firmware.srec").unwrap();
+
+        assert_eq!(decode_intel_hex(&hex), Some(image.clone()));
+        assert_eq!(decode_srec(&srec), Some(image));
+
+        // Bad checksums
+        assert_eq!(decode_intel_hex(":0100000041BF\n"), None);
+        assert_eq!(decode_srec("S1040000410F\n"), None);
+    }
+}
@@ -0,0 +1,66 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{version_sync, BinaryKind, FindVersionError};
+use memmap2::Mmap;
+use std::{fs::File, io::Cursor, path::Path};
+
+/// Get the version for a specific binary file, mapping it in memory and
+/// blocking the current thread.
+///
+/// Large binaries are read without the system calls of each read and seek
+/// done by [`version_from_path`](crate::version_from_path), the kernel paging
+/// in only the parts looked at.
+///
+/// The file must not be modified while the version is looked for, as the
+/// mapping would change under the finders. This must not be called from
+/// within an async runtime.
+pub fn version_mmap(path: &Path, kind: BinaryKind) -> Result<Option<String>, FindVersionError> {
+    let file = File::open(path)?;
+
+    // Mapping a file is unsafe as it may be modified by other processes
+    // while mapped, which is documented above as not supported.
+    #[allow(unsafe_code)]
+    let map = unsafe { Mmap::map(&file)? };
+
+    version_sync(&mut Cursor::new(&map[..]), kind)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn valid() {
+        for (f, k, v) in [
+            (
+                "uboot/arm-spl",
+                BinaryKind::UBoot,
+                Some("2017.11+fslc+ga07698f"),
+            ),
+            (
+                "linuxkernel/arm64-Image",
+                BinaryKind::LinuxKernel,
+                Some("5.10.0-9-arm64"),
+            ),
+            ("linuxkernel/arm-uImage", BinaryKind::UBoot, None),
+        ] {
+            let path = Path::new("tests/fixtures").join(f);
+            assert_eq!(
+                version_mmap(&path, k).unwrap(),
+                v.map(|v| v.to_string()),
+                "{} version mismatch",
+                f
+            );
+        }
+    }
+
+    #[test]
+    fn missing() {
+        assert!(matches!(
+            version_mmap(Path::new("tests/fixtures/missing"), BinaryKind::UBoot),
+            Err(FindVersionError::Io(_))
+        ));
+    }
+}
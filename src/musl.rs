@@ -0,0 +1,75 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{builder::Config, custom::Custom, FindVersionError, VersionFinder};
+use tokio::io::AsyncRead;
+
+/// musl libc version string, as printed by its dynamic linker (e.g: "musl
+/// libc (x86_64) version 1.2.4"), whose `arch` group is the target
+/// architecture and `version` group is the library version.
+pub const MUSL_VERSION_PATTERN: &str =
+    r"musl libc \((?P<arch>[^)]+)\) [Vv]ersion (?P<version>\d+\.\d+\.\d+)";
+
+pub(crate) struct MuslLibrary<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+    config: Config<'a>,
+}
+
+impl<'a, R: AsyncRead + Unpin> MuslLibrary<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        MuslLibrary {
+            buf,
+            config: Config::default(),
+        }
+    }
+
+    pub(crate) fn with_config(self, config: Config<'a>) -> Self {
+        MuslLibrary { config, ..self }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for MuslLibrary<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        Custom::from_reader(self.buf, MUSL_VERSION_PATTERN)
+            .with_config(self.config)
+            .with_group("version")
+            .get_version()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("libc/musl.so", Some("1.2.4")),
+            ("libc/glibc.so", None),
+            ("busybox/busybox", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::MuslLibrary)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+                "{} version mismatch",
+                f
+            );
+        }
+    }
+}
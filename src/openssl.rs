@@ -0,0 +1,89 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{builder::Config, custom::Custom, FindVersionError, VersionFinder};
+use tokio::io::AsyncRead;
+
+// OpenSSL version text, as returned by OpenSSL_version() (e.g: "OpenSSL
+// 3.0.11 19 Sep 2023"). BoringSSL reports the OpenSSL version it is
+// compatible with, followed by its own name (e.g: "OpenSSL 1.1.1
+// (compatible; BoringSSL)").
+const OPENSSL_VERSION_PATTERN: &str =
+    r"OpenSSL (?P<version>\d+\.\d+\.\d+[a-z]?)(?P<boringssl>.*BoringSSL)?";
+
+pub(crate) struct OpenSSL<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+    config: Config<'a>,
+}
+
+impl<'a, R: AsyncRead + Unpin> OpenSSL<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        OpenSSL {
+            buf,
+            config: Config::default(),
+        }
+    }
+
+    pub(crate) fn with_config(self, config: Config<'a>) -> Self {
+        OpenSSL { config, ..self }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for OpenSSL<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        let captures = match Custom::from_reader(self.buf, OPENSSL_VERSION_PATTERN)
+            .with_config(self.config)
+            .get_captures()
+            .await?
+        {
+            Some(captures) => captures,
+            None => return Ok(None),
+        };
+
+        // BoringSSL is told apart from OpenSSL by the build metadata, so the
+        // version still compares as the OpenSSL one it is compatible with
+        Ok(captures[1].as_ref().map(|version| match &captures[2] {
+            Some(_) => format!("{}+BoringSSL", version),
+            None => version.clone(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("openssl/libcrypto.so.1.1", Some("1.1.1w")),
+            ("openssl/libssl.so.1.1", Some("1.1.1w")),
+            ("openssl/libcrypto.so.3", Some("3.0.11")),
+            ("openssl/libssl.so.3", Some("3.0.11")),
+            ("openssl/libcrypto-boringssl.so", Some("1.1.1+BoringSSL")),
+            ("libc/glibc.so", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::OpenSSL)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+                "{} version mismatch",
+                f
+            );
+        }
+    }
+}
@@ -0,0 +1,159 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{FindVersionError, VersionFinder};
+use std::str;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+// Marker starting the sysupgrade image metadata
+const METADATA_MARKER: &[u8] = b"METADATA";
+
+// Metadata field holding the build revision (e.g: "r23497-6637af95aa")
+const VERSION_CODE_KEY: &str = "VERSION_CODE";
+
+/// Find the value of the key in the metadata block, made of `key=value`
+/// lines and ended by the first line which is not one of them.
+fn metadata_value<'a>(metadata: &'a [u8], key: &str) -> Option<&'a str> {
+    metadata
+        .split(|&b| b == b'\n' || b == 0)
+        .map(|line| str::from_utf8(line).ok().map(str::trim))
+        .skip_while(|line| line == &Some(""))
+        .map_while(|line| line?.split_once('='))
+        .find(|(k, _)| k.trim() == key)
+        .map(|(_, v)| v.trim().trim_matches('"'))
+        .filter(|v| !v.is_empty())
+}
+
+/// Versions of an OpenWrt firmware image, as found by
+/// [`openwrt_version`](crate::openwrt_version).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OpenWrtVersion {
+    /// OpenWrt build revision of the sysupgrade metadata (e.g:
+    /// `r23497-6637af95aa`).
+    pub distro: Option<String>,
+    /// Version of the Linux kernel of the image.
+    pub kernel: Option<String>,
+}
+
+pub(crate) struct OpenWrt<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + Unpin> OpenWrt<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        OpenWrt { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for OpenWrt<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        // FIXME: Avoid reading the whole file
+        let mut buffer = Vec::new();
+        self.buf.read_to_end(&mut buffer).await?;
+
+        let start = match buffer
+            .windows(METADATA_MARKER.len())
+            .position(|w| w == METADATA_MARKER)
+        {
+            Some(position) => position + METADATA_MARKER.len(),
+            None => return Ok(None),
+        };
+        debug!("found the sysupgrade metadata at {:#x}", start);
+
+        // A truncated metadata block only provides the fields read so far
+        Ok(metadata_value(&buffer[start..], VERSION_CODE_KEY).map(|v| v.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("openwrt/sysupgrade.bin", Some("r23497-6637af95aa")),
+            ("openwrt/uhttpd_ar.ipk", None),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::OpenWrt)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn combined() {
+        use crate::{helpers, openwrt_version, OpenWrtVersion};
+        use std::io::Cursor;
+
+        let metadata = b"\xffMETADATA\nBOARD=ath79\nVERSION_CODE=r23497-6637af95aa\n\0";
+        let mut firmware = helpers::make_linux_uimage("5.15.134");
+        firmware.extend_from_slice(metadata);
+
+        for (data, distro, kernel) in [
+            (firmware, Some("r23497-6637af95aa"), Some("5.15.134")),
+            (
+                helpers::make_linux_uimage("5.15.134"),
+                None,
+                Some("5.15.134"),
+            ),
+            (metadata.to_vec(), Some("r23497-6637af95aa"), None),
+        ] {
+            assert_eq!(
+                openwrt_version(&mut Cursor::new(data)).await.unwrap(),
+                Some(OpenWrtVersion {
+                    distro: distro.map(|v| v.to_string()),
+                    kernel: kernel.map(|v| v.to_string()),
+                }),
+            );
+        }
+
+        assert_eq!(
+            openwrt_version(&mut fixture("uboot/arm-spl").await)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn truncated() {
+        for (data, v) in [
+            (
+                &b"\xffMETADATA\nBOARD=ath79\nVERSION_CODE=r23497"[..],
+                Some("r23497"),
+            ),
+            (&b"\xffMETADATA\nBOARD=ath79\nVERSION_CO"[..], None),
+            (
+                &b"\xffMETADATA\nBOARD=ath79\n\xff\xffVERSION_CODE=r1"[..],
+                None,
+            ),
+            (&b"\xffMETADATA"[..], None),
+        ] {
+            assert_eq!(
+                version(&mut std::io::Cursor::new(data), BinaryKind::OpenWrt)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+}
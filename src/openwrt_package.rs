@@ -0,0 +1,85 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    archive::read_archive_file, strings::IntoStringsIter, FindVersionError, VersionFinder,
+};
+use regex::Regex;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+// Match an archive member, ignoring the leading './' of its path.
+fn member(name: &str) -> impl Fn(&str) -> bool + '_ {
+    move |path| path.trim_start_matches("./") == name
+}
+
+pub(crate) struct OpenwrtPackage<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + Unpin> OpenwrtPackage<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        OpenwrtPackage { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for OpenwrtPackage<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        // FIXME: Avoid reading the whole file
+        let mut buffer = Vec::new();
+        self.buf.read_to_end(&mut buffer).await?;
+
+        // The package is either a tar.gz (as built by OpenWrt) or an ar
+        // archive (as built by dpkg), both containing the control.tar.gz
+        // archive with the package metadata.
+        let control = match read_archive_file(&buffer, member("control.tar.gz"))
+            .and_then(|control| read_archive_file(&control, member("control")))
+        {
+            Some(control) => control,
+            None => return Ok(None),
+        };
+
+        let re = Regex::new(r"^Version:\s*(?P<version>[^\n]+)").unwrap();
+        for line in control.into_strings_iter() {
+            if let Some(v) = re.captures(&line).and_then(|c| c.name("version")) {
+                return Ok(Some(v.as_str().trim().to_string()));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("openwrt/dropbear_2020.81-2_arm.ipk", Some("2020.81-2")),
+            ("openwrt/uhttpd_ar.ipk", Some("2021-03-21-15346de8-2")),
+            ("helm/nginx-9.4.1.tgz", None),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::OpenwrtPackage)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+}
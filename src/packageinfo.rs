@@ -0,0 +1,73 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{strings::IntoStringsIter, FindVersionError, VersionFinder};
+use regex::Regex;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+pub(crate) struct PackageInfo<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + Unpin> PackageInfo<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        PackageInfo { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for PackageInfo<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        // FIXME: Avoid reading the whole file
+        let mut buffer = Vec::new();
+        self.buf.read_to_end(&mut buffer).await?;
+
+        // The version is required to have at least one dot, to reduce the
+        // false positives of this heuristic.
+        let patterns = [
+            // package.json
+            Regex::new(r#""version"\s*:\s*"(?P<version>[^".]+\.[^"]+)""#).unwrap(),
+            // Cargo.toml and setup.py
+            Regex::new(r#"^\s*version\s*=\s*"(?P<version>[^".]+\.[^"]+)""#).unwrap(),
+        ];
+        for line in buffer.into_strings_iter() {
+            if let Some(v) = patterns
+                .iter()
+                .find_map(|re| re.captures(&line).and_then(|c| c.name("version")))
+            {
+                return Ok(Some(v.as_str().to_string()));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/packageinfo/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[("electron-asar.bin", "2.4.1"), ("cargo-toml.bin", "0.8.3")] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::PackageInfo)
+                    .await
+                    .unwrap(),
+                Some(v.to_string()),
+            );
+        }
+    }
+}
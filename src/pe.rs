@@ -0,0 +1,280 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{FindVersionError, VersionFinder};
+use std::io::SeekFrom;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+// DOS header Magic Number ("MZ"), little-endian
+const DOS_MAGIC: u16 = 0x5A4D;
+
+// PE signature ("PE\0\0"), little-endian
+const PE_SIGNATURE: u32 = 0x0000_4550;
+
+// Optional header Magic Numbers of 32-bit (PE32) and 64-bit (PE32+) images
+const PE32_MAGIC: u16 = 0x010B;
+const PE32_PLUS_MAGIC: u16 = 0x020B;
+
+// Index of the resource table in the optional header data directories
+const IMAGE_DIRECTORY_ENTRY_RESOURCE: u32 = 2;
+
+// Resource type of the version information (RT_VERSION)
+const RT_VERSION: u32 = 16;
+
+// Set in the resource directory entries pointing to another directory
+const IMAGE_RESOURCE_DATA_IS_DIRECTORY: u32 = 0x8000_0000;
+
+// VS_FIXEDFILEINFO signature
+const VS_FFI_SIGNATURE: u32 = 0xFEEF_04BD;
+
+// Largest version information resource looked at
+const MAX_VERSION_INFO_SIZE: u32 = 0x10000;
+
+/// A section of the image, mapping its virtual addresses to the file.
+struct Section {
+    virtual_address: u32,
+    virtual_size: u32,
+    raw_offset: u32,
+}
+
+pub(crate) struct WindowsPE<'a, R: AsyncRead + AsyncSeek + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> WindowsPE<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        WindowsPE { buf }
+    }
+
+    /// Read the first entry of the resource directory at the given offset
+    /// whose identifier is `id`, or the first one when unset, as its
+    /// `OffsetToData` field.
+    async fn read_directory_entry(
+        &mut self,
+        offset: u64,
+        id: Option<u32>,
+    ) -> Result<Option<u32>, FindVersionError> {
+        // Taken from: https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#resource-directory-table
+        //
+        // Offset  Size  Name                     Meaning
+        // 0000    4     Characteristics          Reserved
+        // 0004    4     TimeDateStamp            Creation time of the resource data
+        // 0008    4     Version                  Major and minor versions
+        // 000C    2     NumberOfNamedEntries     Entries using a name as identifier
+        // 000E    2     NumberOfIdEntries        Entries using a number as identifier
+        // 0010    ...   Entries                  Name or Id (4), OffsetToData (4)
+        //
+        // The named entries come first, and are never the ones looked for.
+        self.buf.seek(SeekFrom::Start(offset + 0x0C)).await?;
+        let named = self.buf.read_u16_le().await?;
+        let ids = self.buf.read_u16_le().await?;
+        self.buf
+            .seek(SeekFrom::Current(8 * i64::from(named)))
+            .await?;
+        for _ in 0..ids {
+            let entry_id = self.buf.read_u32_le().await?;
+            let data = self.buf.read_u32_le().await?;
+            if id.is_none() || id == Some(entry_id) {
+                return Ok(Some(data));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Map a relative virtual address to the file offset holding it.
+fn rva_to_offset(sections: &[Section], rva: u32) -> Option<u64> {
+    sections
+        .iter()
+        .find(|s| rva >= s.virtual_address && rva - s.virtual_address < s.virtual_size)
+        .map(|s| u64::from(rva - s.virtual_address) + u64::from(s.raw_offset))
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> VersionFinder for WindowsPE<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        // The DOS header points to the PE signature at offset 0x003C
+        self.buf.seek(SeekFrom::Start(0)).await?;
+        if self.buf.read_u16_le().await? != DOS_MAGIC {
+            return Ok(None);
+        }
+        self.buf.seek(SeekFrom::Start(0x3C)).await?;
+        let pe_offset = u64::from(self.buf.read_u32_le().await?);
+
+        // Taken from: https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#coff-file-header-object-and-image
+        //
+        // Offset  Size  Name                  Meaning
+        // 0000    4     Signature             "PE\0\0"
+        // 0004    2     Machine               Target machine type
+        // 0006    2     NumberOfSections      Number of entries in the section table
+        // ...
+        // 0014    2     SizeOfOptionalHeader  Size of the optional header
+        // 0016    2     Characteristics       Attributes of the image
+        // 0018    ...   Optional header       Starting with its Magic Number
+        self.buf.seek(SeekFrom::Start(pe_offset)).await?;
+        if self.buf.read_u32_le().await? != PE_SIGNATURE {
+            return Ok(None);
+        }
+        self.buf.seek(SeekFrom::Start(pe_offset + 0x06)).await?;
+        let sections_count = self.buf.read_u16_le().await?;
+        self.buf.seek(SeekFrom::Start(pe_offset + 0x14)).await?;
+        let optional_header_size = u64::from(self.buf.read_u16_le().await?);
+        let optional_header = pe_offset + 0x18;
+
+        // The data directories, preceded by their count, follow the fields
+        // of the optional header, whose size differs for PE32+ images
+        self.buf.seek(SeekFrom::Start(optional_header)).await?;
+        let directories = match self.buf.read_u16_le().await? {
+            PE32_MAGIC => optional_header + 0x60,
+            PE32_PLUS_MAGIC => optional_header + 0x70,
+            _ => return Ok(None),
+        };
+        self.buf.seek(SeekFrom::Start(directories - 4)).await?;
+        if self.buf.read_u32_le().await? <= IMAGE_DIRECTORY_ENTRY_RESOURCE {
+            return Ok(None);
+        }
+        self.buf
+            .seek(SeekFrom::Current(
+                8 * i64::from(IMAGE_DIRECTORY_ENTRY_RESOURCE),
+            ))
+            .await?;
+        let resources_rva = self.buf.read_u32_le().await?;
+        if resources_rva == 0 {
+            return Ok(None);
+        }
+
+        // Section table entry layout:
+        //   Name (8), VirtualSize (4), VirtualAddress (4), SizeOfRawData (4),
+        //   PointerToRawData (4), ... (16)
+        self.buf
+            .seek(SeekFrom::Start(optional_header + optional_header_size))
+            .await?;
+        let mut sections = Vec::new();
+        for _ in 0..sections_count {
+            let mut name = [0; 8];
+            self.buf.read_exact(&mut name).await?;
+            let virtual_size = self.buf.read_u32_le().await?;
+            let virtual_address = self.buf.read_u32_le().await?;
+            let raw_size = self.buf.read_u32_le().await?;
+            let raw_offset = self.buf.read_u32_le().await?;
+            self.buf.seek(SeekFrom::Current(16)).await?;
+            sections.push(Section {
+                virtual_address,
+                virtual_size: virtual_size.max(raw_size),
+                raw_offset,
+            });
+        }
+
+        // The resources are a tree of directories, by type, name and
+        // language, the offsets being relative to the resource table
+        let resources = match rva_to_offset(&sections, resources_rva) {
+            Some(offset) => offset,
+            None => return Ok(None),
+        };
+        let mut entry = None;
+        for id in [Some(RT_VERSION), None, None] {
+            let offset = match entry {
+                None => resources,
+                Some(entry) if entry & IMAGE_RESOURCE_DATA_IS_DIRECTORY != 0 => {
+                    resources + u64::from(entry & !IMAGE_RESOURCE_DATA_IS_DIRECTORY)
+                }
+                Some(_) => return Ok(None),
+            };
+            entry = match self.read_directory_entry(offset, id).await? {
+                Some(entry) => Some(entry),
+                None => return Ok(None),
+            };
+        }
+        let data_entry = match entry {
+            Some(entry) if entry & IMAGE_RESOURCE_DATA_IS_DIRECTORY == 0 => {
+                resources + u64::from(entry)
+            }
+            _ => return Ok(None),
+        };
+
+        // Resource data entry layout:
+        //   OffsetToData (4, relative virtual address), Size (4), CodePage (4),
+        //   Reserved (4)
+        self.buf.seek(SeekFrom::Start(data_entry)).await?;
+        let data_rva = self.buf.read_u32_le().await?;
+        let data_size = self.buf.read_u32_le().await?;
+        let data = match rva_to_offset(&sections, data_rva) {
+            Some(offset) => offset,
+            None => return Ok(None),
+        };
+        debug!(
+            "found version information resource of {} bytes at offset {:#x}",
+            data_size, data
+        );
+
+        // The VS_VERSIONINFO structure starts with its length and key
+        // ("VS_VERSION_INFO" in UTF-16), padded to 32-bit, before the
+        // VS_FIXEDFILEINFO, so the latter is looked for by its signature
+        //
+        // VS_FIXEDFILEINFO layout:
+        //   dwSignature (4), dwStrucVersion (4), dwFileVersionMS (4),
+        //   dwFileVersionLS (4), ...
+        self.buf.seek(SeekFrom::Start(data)).await?;
+        let mut info = Vec::new();
+        (&mut *self.buf)
+            .take(u64::from(data_size.min(MAX_VERSION_INFO_SIZE)))
+            .read_to_end(&mut info)
+            .await?;
+        let fixed = info
+            .chunks_exact(4)
+            .position(|w| w == VS_FFI_SIGNATURE.to_le_bytes())
+            .map(|i| i * 4);
+        let version = match fixed.and_then(|i| info.get(i + 8..i + 16)) {
+            Some(version) => version,
+            None => return Ok(None),
+        };
+        let ms = u32::from_le_bytes([version[0], version[1], version[2], version[3]]);
+        let ls = u32::from_le_bytes([version[4], version[5], version[6], version[7]]);
+
+        Ok(Some(format!(
+            "{}.{}.{}.{}",
+            ms >> 16,
+            ms & 0xFFFF,
+            ls >> 16,
+            ls & 0xFFFF
+        )))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("pe/updater64.exe", Some("10.0.19041.1")),
+            ("pe/updater32.exe", Some("10.0.19041.1")),
+            ("pe/no-resources.exe", None),
+            ("go/hello", None),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::WindowsPE)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+                "{} version mismatch",
+                f
+            );
+        }
+    }
+}
@@ -0,0 +1,88 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    archive::read_archive_file, strings::IntoStringsIter, FindVersionError, VersionFinder,
+};
+use regex::Regex;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+// ZIP local file header Magic Number
+pub(crate) const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+// The package metadata is stored as '<name>-<version>.dist-info/METADATA'
+fn is_metadata(path: &str) -> bool {
+    match path.split('/').collect::<Vec<_>>()[..] {
+        [dir, "METADATA"] => dir.ends_with(".dist-info"),
+        _ => false,
+    }
+}
+
+pub(crate) struct PythonWheel<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + Unpin> PythonWheel<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        PythonWheel { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for PythonWheel<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        // FIXME: Avoid reading the whole file
+        let mut buffer = Vec::new();
+        self.buf.read_to_end(&mut buffer).await?;
+        if !buffer.starts_with(ZIP_MAGIC) {
+            return Ok(None);
+        }
+
+        let metadata = match read_archive_file(&buffer, is_metadata) {
+            Some(metadata) => metadata,
+            None => return Ok(None),
+        };
+
+        let re = Regex::new(r"^Version:\s*(?P<version>[^\n\r]+)").unwrap();
+        for line in metadata.into_strings_iter() {
+            if let Some(v) = re.captures(&line).and_then(|c| c.name("version")) {
+                return Ok(Some(v.as_str().trim().to_string()));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("wheel/requests-2.26.0-py2.py3-none-any.whl", Some("2.26.0")),
+            ("helm/nginx-9.4.1.tgz", None),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::PythonWheel)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{FindVersionError, VersionFinder};
+use regex::bytes::Regex;
+use std::str;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+// Bootloader release date, following its marker (e.g: "BOOTLOADER RELEASE
+// VERSION 2023/01/11")
+const RPI_EEPROM_VERSION_PATTERN: &str =
+    r"BOOTLOADER RELEASE VERSION[\s:=]*(?P<version>\d{4}/\d{2}/\d{2})";
+
+// The release date is always found at the begin of the image
+const RPI_EEPROM_SCAN_SIZE: u64 = 0x1000;
+
+pub(crate) struct RPi4Eeprom<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + Unpin> RPi4Eeprom<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        RPi4Eeprom { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for RPi4Eeprom<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        let mut data = Vec::new();
+        (&mut *self.buf)
+            .take(RPI_EEPROM_SCAN_SIZE)
+            .read_to_end(&mut data)
+            .await?;
+
+        let re = Regex::new(RPI_EEPROM_VERSION_PATTERN).unwrap();
+        Ok(re
+            .captures(&data)
+            .and_then(|c| c.name("version"))
+            .and_then(|v| str::from_utf8(v.as_bytes()).ok())
+            .map(str::to_string))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("rpi/pieeprom.upd", Some("2023/01/11")),
+            ("videocore/start4.elf", None),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::RPi4Eeprom)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+                "{} version mismatch",
+                f
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn beyond_scan_size() {
+        let mut data = vec![0; 0x1000];
+        data.extend_from_slice(b"BOOTLOADER RELEASE VERSION 2023/01/11\0");
+
+        assert_eq!(
+            version(&mut std::io::Cursor::new(data), BinaryKind::RPi4Eeprom)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use regex::Regex;
+use semver::{BuildMetadata, Prerelease, Version};
+use std::cmp::Ordering;
+
+/// Result of comparing two version strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionOrdering {
+    /// The first version is older than the second one.
+    Older,
+    /// Both versions have the same precedence.
+    Same,
+    /// The first version is newer than the second one.
+    Newer,
+    /// At least one of the versions could not be parsed.
+    Incomparable,
+}
+
+/// Turn a dot separated list of identifiers into a valid SemVer one, replacing
+/// the characters SemVer does not allow by `-` and dropping empty
+/// identifiers. Leading zeros are only stripped from numeric pre-release
+/// identifiers, as they are valid in build metadata.
+fn sanitize(identifiers: &str, strip_zeros: bool) -> String {
+    identifiers
+        .split('.')
+        .filter(|i| !i.is_empty())
+        .map(|i| {
+            let i: String = i
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+                .collect();
+            if strip_zeros && i.bytes().all(|b| b.is_ascii_digit()) {
+                let stripped = i.trim_start_matches('0');
+                return if stripped.is_empty() { "0" } else { stripped }.to_string();
+            }
+            i
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Parse a version string found in a binary as a SemVer version.
+///
+/// Embedded versions seldom follow SemVer strictly, so they are mapped as:
+///
+/// * a leading `v` is ignored;
+/// * the first three numeric components, separated by `.`, are the major,
+///   minor and patch versions, missing ones being `0` and leading zeros
+///   being ignored (e.g: `2023.10` is `2023.10.0` and `2019.04` is
+///   `2019.4.0`);
+/// * further numeric components are kept as the first build metadata
+///   identifiers (e.g: `1.2.3.4` is `1.2.3+4`), so they do not affect the
+///   precedence;
+/// * what follows the numeric components, up to the first `+`, is the
+///   pre-release, without its leading separator (e.g: `-`, `_` or `~`), so
+///   vendor suffixes make the version lower than the plain one (e.g:
+///   `4.1.15-1.2.0` is older than `4.1.15`);
+/// * what follows the first `+` is the build metadata, further `+` being
+///   taken as identifier separators (e.g: `2017.11+fslc+ga07698f` is
+///   `2017.11.0+fslc.ga07698f`);
+/// * characters SemVer does not allow in identifiers are replaced by `-`.
+///
+/// `None` is returned when the string does not start with a number.
+///
+/// ```
+/// use find_binary_version::parse_version;
+///
+/// let version = parse_version("4.1.15-1.2.0+gabcd").unwrap();
+/// assert_eq!(version.to_string(), "4.1.15-1.2.0+gabcd");
+/// ```
+pub fn parse_version(s: &str) -> Option<Version> {
+    let re = Regex::new(r"^[vV]?(\d+)(?:\.(\d+))?(?:\.(\d+))?((?:\.\d+)*)(.*)$").unwrap();
+    let captures = re.captures(s.trim())?;
+    let number = |n: usize| {
+        captures
+            .get(n)
+            .map_or(Some(0), |m| m.as_str().parse::<u64>().ok())
+    };
+
+    let mut version = Version::new(number(1)?, number(2)?, number(3)?);
+    let (pre, build) = match captures[5].split_once('+') {
+        Some((pre, build)) => (pre, build.replace('+', ".")),
+        None => (&captures[5], String::new()),
+    };
+    let pre = pre.trim_start_matches(|c: char| !c.is_ascii_alphanumeric());
+    let build = format!("{}.{}", &captures[4], build);
+
+    version.pre = Prerelease::new(&sanitize(pre, true)).ok()?;
+    version.build = BuildMetadata::new(&sanitize(&build, false)).ok()?;
+    Some(version)
+}
+
+/// Compare two version strings, once parsed by [`parse_version`].
+///
+/// The build metadata is ignored, as done by SemVer.
+pub fn compare_versions(a: &str, b: &str) -> VersionOrdering {
+    match (parse_version(a), parse_version(b)) {
+        (Some(a), Some(b)) => match a.cmp_precedence(&b) {
+            Ordering::Less => VersionOrdering::Older,
+            Ordering::Equal => VersionOrdering::Same,
+            Ordering::Greater => VersionOrdering::Newer,
+        },
+        _ => VersionOrdering::Incomparable,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        for (s, v) in [
+            ("2023.10", Some("2023.10.0")),
+            ("4.1.15-1.2.0+gabcd", Some("4.1.15-1.2.0+gabcd")),
+            ("2017.11+fslc+ga07698f", Some("2017.11.0+fslc.ga07698f")),
+            (
+                "2019.04-00014-gc93ced78db",
+                Some("2019.4.0-00014-gc93ced78db"),
+            ),
+            ("5.10.0-9-arm64", Some("5.10.0-9-arm64")),
+            ("v1.36.1", Some("1.36.1")),
+            ("1.2.3.4", Some("1.2.3+4")),
+            ("2.8-rc0", Some("2.8.0-rc0")),
+            ("4.19.0_rc1.007", Some("4.19.0-rc1.7")),
+            ("r23497-6637af95aa", None),
+            ("", None),
+        ] {
+            assert_eq!(
+                parse_version(s).map(|v| v.to_string()),
+                v.map(|v| v.to_string()),
+                "{}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn compare() {
+        for (a, b, ordering) in [
+            ("2023.10", "2023.04", VersionOrdering::Newer),
+            ("2017.11+fslc+ga07698f", "2017.11", VersionOrdering::Same),
+            ("4.1.15-1.2.0+gabcd", "4.1.15", VersionOrdering::Older),
+            ("1.9.0", "1.10.0", VersionOrdering::Older),
+            ("SNAPSHOT", "1.0", VersionOrdering::Incomparable),
+        ] {
+            assert_eq!(compare_versions(a, b), ordering, "{} and {}", a, b);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{uimage::format_date, FindVersionError, VersionFinder};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+// SquashFS Magic Number ("hsqs"), as read in the filesystem endianness
+const SQUASHFS_MAGIC: u32 = 0x7371_7368;
+
+// Size of the superblock part holding the fields looked at
+const SQUASHFS_HEADER_SIZE: usize = 0x30;
+
+pub(crate) struct SquashFS<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + Unpin> SquashFS<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        SquashFS { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for SquashFS<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        let mut header = [0; SQUASHFS_HEADER_SIZE];
+        self.buf.read_exact(&mut header).await?;
+
+        // The filesystem may be stored in either endianness, which is told
+        // by the magic number
+        let magic = [header[0], header[1], header[2], header[3]];
+        let big_endian = match magic {
+            _ if u32::from_le_bytes(magic) == SQUASHFS_MAGIC => false,
+            _ if u32::from_be_bytes(magic) == SQUASHFS_MAGIC => true,
+            _ => return Ok(None),
+        };
+        let u16_at = |offset: usize| {
+            let bytes = [header[offset], header[offset + 1]];
+            match big_endian {
+                true => u16::from_be_bytes(bytes),
+                false => u16::from_le_bytes(bytes),
+            }
+        };
+        let u32_at = |offset: usize| {
+            let bytes = [
+                header[offset],
+                header[offset + 1],
+                header[offset + 2],
+                header[offset + 3],
+            ];
+            match big_endian {
+                true => u32::from_be_bytes(bytes),
+                false => u32::from_le_bytes(bytes),
+            }
+        };
+
+        // Taken from: https://dr-emann.github.io/squashfs/squashfs.html#_the_superblock
+        //
+        // Offset  Size  Name           Meaning
+        // 0000    4     magic          "hsqs"
+        // 0004    4     inode_count    Number of inodes
+        // 0008    4     mkfs_time      Creation time, in seconds since the Unix epoch
+        // ...
+        // 001C    2     version_major  Major version of the format
+        // 001E    2     version_minor  Minor version of the format
+        //
+        // The format versions older than 4.0 store the creation time at
+        // offset 0x0027 instead, after the fields they had in its place.
+        let major = u16_at(0x1C);
+        let minor = u16_at(0x1E);
+        let mkfs_time = match major {
+            4.. => u32_at(0x08),
+            _ => u32_at(0x27),
+        };
+        debug!(
+            "read SquashFS {}.{} superblock (big endian: {}, mkfs_time {})",
+            major, minor, big_endian, mkfs_time
+        );
+
+        Ok(Some(format!(
+            "{}.{} (built {})",
+            major,
+            minor,
+            format_date(mkfs_time.into())
+        )))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            (
+                "squashfs/rootfs-v4.squashfs",
+                Some("4.0 (built 2024-01-12)"),
+            ),
+            (
+                "squashfs/rootfs-v3-be.squashfs",
+                Some("3.1 (built 2009-06-04)"),
+            ),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::SquashFS)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+                "{} version mismatch",
+                f
+            );
+        }
+    }
+}
@@ -10,10 +10,13 @@
 // Reference code:
 //  https://gitlab.redox-os.org/redox-os/binutils/blob/966c6f039e20d56cec369621065646c4f21cbd61/src/strings.rs
 
-use std::{io::Read, slice, str};
+use std::{
+    io::{ErrorKind, Read},
+    str,
+};
 
 /// A trait for characters/bytes that can be printable.
-pub(crate) trait IsPrintable {
+pub trait IsPrintable {
     /// Is this character printable?
     fn is_printable(&self) -> bool;
 }
@@ -26,96 +29,369 @@ impl IsPrintable for u8 {
     }
 }
 
-/// A buffer tracking the previous printable characters.
-#[derive(Copy, Clone)]
-struct Trailing {
-    chars: [u8; 4],
-    current: usize,
+/// A byte of ISO-8859-1 (Latin-1) encoded text.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ExtendedLatin1(pub(crate) u8);
+
+impl IsPrintable for ExtendedLatin1 {
+    #[inline]
+    fn is_printable(&self) -> bool {
+        // Is an ASCII in a printable range or a Latin-1 supplement character
+        // (e.g: '©' or 'é'), skipping the C1 control codes
+        self.0.is_printable() || self.0 >= 0xa0
+    }
 }
 
-#[allow(dead_code)]
-impl Trailing {
+impl IsPrintable for char {
     #[inline]
-    fn new() -> Trailing {
-        Trailing {
-            chars: [0; 4],
-            current: 0,
+    fn is_printable(&self) -> bool {
+        // Accept any non-control Unicode character, including multi-byte ones
+        !self.is_control() && !self.is_ascii_control()
+    }
+}
+
+// Size of the scratch buffer the reader is read into
+const SCRATCH_SIZE: usize = 0x2000;
+
+/// Configuration of the strings iterator.
+#[derive(Debug, Clone, Copy)]
+pub struct StringsConfig {
+    /// Minimum amount of printable characters for a string to be yielded, 4
+    /// by default as done by binutils' `strings`.
+    pub min_length: usize,
+}
+
+impl Default for StringsConfig {
+    fn default() -> Self {
+        StringsConfig { min_length: 4 }
+    }
+}
+
+/// Wraps a reader to provide a strings iterator, as done by binutils'
+/// `strings`.
+///
+/// Strings are runs of printable ASCII characters of the configured minimum
+/// length (4 by default) or more, ended by any other byte. The last string of
+/// the reader does not need to be ended, but it is discarded as any other
+/// when it is shorter than the minimum length. Errors reading from the reader
+/// end the iteration.
+///
+/// ```
+/// use find_binary_version::{Strings, StringsConfig};
+///
+/// let binary = b"\0\x01U-Boot 2022.04\0abc\0\xffBarebox";
+/// let strings: Vec<String> = Strings::with_config(&binary[..], StringsConfig { min_length: 4 }).collect();
+/// assert_eq!(strings, ["U-Boot 2022.04", "Barebox"]);
+/// ```
+#[derive(Debug)]
+pub struct Strings<R> {
+    reader: R,
+    min_len: usize,
+    max_len: usize,
+    truncate_len: usize,
+    extended: bool,
+    // Bytes read from the reader and not looked at yet
+    scratch: Box<[u8]>,
+    pos: usize,
+    filled: usize,
+    // Printable bytes of the string being read
+    stanza: Vec<u8>,
+    // Bytes looked at so far, and offset of the last string yielded
+    bytes_consumed: u64,
+    start: u64,
+    // String read ahead by `peek` and not yielded yet
+    peeked: Option<String>,
+}
+
+impl<R> Strings<R> {
+    /// Create a strings iterator using the given configuration.
+    pub fn with_config(reader: R, config: StringsConfig) -> Self {
+        Strings {
+            reader,
+            min_len: config.min_length,
+            max_len: usize::MAX,
+            truncate_len: usize::MAX,
+            extended: false,
+            scratch: vec![0; SCRATCH_SIZE].into_boxed_slice(),
+            pos: 0,
+            filled: 0,
+            stanza: Vec::new(),
+            bytes_consumed: 0,
+            start: 0,
+            peeked: None,
         }
     }
 
-    #[inline]
-    fn set(&mut self, b: u8) -> bool {
-        self.chars[self.current] = b;
-        self.current += 1;
+    /// Create a strings iterator also accepting the ISO-8859-1 (Latin-1)
+    /// printable characters, which are decoded to their Unicode counterpart.
+    pub fn with_extended_charset(reader: R) -> Self {
+        Strings {
+            extended: true,
+            ..Strings::with_config(reader, StringsConfig::default())
+        }
+    }
 
-        self.is_complete()
+    /// Only yield strings whose length is in the `[min_len, max_len]` range.
+    ///
+    /// Strings longer than `max_len` are split at `max_len` boundaries, and
+    /// strings shorter than `min_len` (including the trailing part of a split
+    /// string) are discarded.
+    pub fn with_context_hint(mut self, min_len: usize, max_len: usize) -> Self {
+        self.min_len = min_len;
+        self.max_len = max_len;
+        self
     }
 
-    #[inline]
-    fn reset(&mut self) {
-        self.current = 0;
+    /// Truncate the strings longer than `max_length` characters, if set,
+    /// dropping the rest of their printable characters instead of yielding
+    /// them as strings of their own.
+    pub fn with_max_length(mut self, max_length: Option<usize>) -> Self {
+        self.truncate_len = max_length.unwrap_or(usize::MAX);
+        self
     }
+}
 
-    #[inline]
-    fn is_complete(self) -> bool {
-        self.current == 4
+/// Wraps a strings iterator to also yield the offset of each string, from
+/// the position of the reader when it was created.
+pub(crate) struct StringsWithOffset<R>(Strings<R>);
+
+impl<R: Read> Iterator for StringsWithOffset<R> {
+    type Item = (u64, String);
+
+    fn next(&mut self) -> Option<(u64, String)> {
+        let stanza = self.0.next()?;
+        Some((self.0.start, stanza))
     }
+}
 
-    #[inline]
-    fn chars(self) -> [u8; 4] {
-        self.chars
+pub(crate) trait StringsExt: Read + Sized {
+    /// Provide a strings iterator yielding the offset of each string, for
+    /// strings of `min_length` printable characters or more.
+    fn strings_with_offset(self, min_length: usize) -> StringsWithOffset<Self> {
+        StringsWithOffset(Strings::with_config(self, StringsConfig { min_length }))
     }
 }
 
-/// Wraps a reader to provide a strings iterator.
-pub(crate) struct Strings<R>(R);
+impl<T: Read> StringsExt for T {}
 
-pub(crate) trait IntoStringsIter<T> {
+/// Conversion of a reader into a strings iterator.
+pub trait IntoStringsIter<T> {
+    /// Provide a strings iterator using the default configuration.
     fn into_strings_iter(self) -> Strings<T>;
+    /// Provide a strings iterator over UTF-8 encoded data.
+    fn into_utf8_strings_iter(self) -> StringsUtf8<T>;
 }
 
 impl<T: Read> IntoStringsIter<T> for T {
     fn into_strings_iter(self) -> Strings<T> {
-        Strings(self)
+        Strings::with_config(self, StringsConfig::default())
+    }
+
+    fn into_utf8_strings_iter(self) -> StringsUtf8<T> {
+        StringsUtf8 {
+            reader: self,
+            buffer: Vec::new(),
+            pos: 0,
+            eof: false,
+        }
+    }
+}
+
+impl<R: Read> Strings<R> {
+    /// Refill the scratch buffer, returning `false` once the reader is over.
+    fn fill(&mut self) -> bool {
+        loop {
+            match self.reader.read(&mut self.scratch) {
+                Ok(n) => {
+                    self.pos = 0;
+                    self.filled = n;
+                    return n > 0;
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => return false,
+            }
+        }
+    }
+
+    /// Get the next string without advancing the iterator, as done by
+    /// [`Peekable::peek`](std::iter::Peekable::peek).
+    ///
+    /// ```
+    /// use find_binary_version::IntoStringsIter;
+    ///
+    /// let mut strings = b"\0version\x01v1.2.3\0".into_strings_iter();
+    /// assert_eq!(strings.peek(), Some("version"));
+    /// assert_eq!(strings.next().as_deref(), Some("version"));
+    /// assert_eq!(strings.next().as_deref(), Some("v1.2.3"));
+    /// assert_eq!(strings.peek(), None);
+    /// ```
+    pub fn peek(&mut self) -> Option<&str> {
+        if self.peeked.is_none() {
+            self.peeked = self.read_next();
+        }
+        self.peeked.as_deref()
+    }
+
+    /// Turn the string read so far into a `String`, clearing it.
+    fn take_stanza(&mut self) -> String {
+        // Latin-1 is the first block of Unicode, so bytes map to the
+        // characters of the same code point, which also holds for ASCII
+        self.stanza.drain(..).map(char::from).collect()
     }
 }
 
 /// Provides an iterator to a stream of bytes and output printable
-/// strings of length 4 or more.
+/// strings of the configured minimum length (4 by default) or more.
 impl<R: Read> Iterator for Strings<R> {
     type Item = String;
 
+    fn next(&mut self) -> Option<String> {
+        match self.peeked.take() {
+            Some(stanza) => Some(stanza),
+            None => self.read_next(),
+        }
+    }
+}
+
+impl<R: Read> Strings<R> {
+    /// Read the next string from the reader.
+    fn read_next(&mut self) -> Option<String> {
+        // Strings are one byte per character, so the lengths are the same
+        // in bytes and in characters. Empty strings are never yielded.
+        let min_len = self.min_len.max(1);
+
+        loop {
+            if self.pos == self.filled && !self.fill() {
+                if self.stanza.len() < min_len {
+                    self.stanza.clear();
+                    return None;
+                }
+
+                return Some(self.take_stanza());
+            }
+
+            let mut ready = false;
+            for &byte in &self.scratch[self.pos..self.filled] {
+                self.pos += 1;
+                self.bytes_consumed += 1;
+
+                let printable = if self.extended {
+                    ExtendedLatin1(byte).is_printable()
+                } else {
+                    byte.is_printable()
+                };
+
+                if printable {
+                    if self.stanza.is_empty() {
+                        self.start = self.bytes_consumed - 1;
+                    }
+                    if self.stanza.len() < self.truncate_len {
+                        self.stanza.push(byte);
+                    }
+
+                    // Split the string when reaching the maximum length
+                    if self.stanza.len() >= self.max_len {
+                        ready = true;
+                        break;
+                    }
+                } else if self.stanza.len() >= min_len {
+                    ready = true;
+                    break;
+                } else {
+                    // Too short, discard it and look for the next one
+                    self.stanza.clear();
+                }
+            }
+
+            if ready {
+                return Some(self.take_stanza());
+            }
+        }
+    }
+}
+
+/// Wraps a reader to provide a strings iterator over UTF-8 encoded data,
+/// yielding strings of 4 or more printable characters.
+#[derive(Debug)]
+pub struct StringsUtf8<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    pos: usize,
+    eof: bool,
+}
+
+impl<R: Read> StringsUtf8<R> {
+    /// Decode the next character, returning `Some(None)` for invalid bytes.
+    fn next_char(&mut self) -> Option<Option<char>> {
+        // Keep enough bytes buffered to decode the longest UTF-8 sequence, so
+        // sequences split among reads are not lost.
+        while self.buffer.len() - self.pos < 4 && !self.eof {
+            self.buffer.drain(..self.pos);
+            self.pos = 0;
+
+            let mut chunk = [0; 0x200];
+            match self.reader.read(&mut chunk) {
+                Ok(0) => self.eof = true,
+                Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => self.eof = true,
+            }
+        }
+
+        let pending = &self.buffer[self.pos..];
+        let width = match *pending.first()? {
+            0x00..=0x7f => 1,
+            0xc2..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf4 => 4,
+            _ => 0,
+        };
+
+        match pending.get(..width).map(str::from_utf8) {
+            Some(Ok(c)) if width > 0 => {
+                self.pos += width;
+                Some(c.chars().next())
+            }
+            _ => {
+                // Invalid or truncated sequence, skip the leading byte
+                self.pos += 1;
+                Some(None)
+            }
+        }
+    }
+}
+
+/// Provides an iterator to a stream of UTF-8 bytes and output printable
+/// strings of 4 or more characters.
+impl<R: Read> Iterator for StringsUtf8<R> {
+    type Item = String;
+
     fn next(&mut self) -> Option<String> {
         let mut stanza = String::new();
-        let mut trailing = Trailing::new();
-        let mut byte = 0;
+        let mut len = 0;
 
         loop {
-            match self.0.read(slice::from_mut(&mut byte)) {
-                Ok(0) => {
-                    if stanza.is_empty() {
+            match self.next_char() {
+                None => {
+                    if len < 4 {
                         return None;
                     }
 
                     return Some(stanza);
                 }
-                Ok(_) => {
-                    if byte.is_printable() {
-                        if trailing.is_complete() {
-                            stanza.push_str(str::from_utf8(&[byte]).unwrap());
-                        } else if trailing.set(byte) {
-                            stanza.push_str(str::from_utf8(&trailing.chars()).unwrap());
-                        }
-                    } else {
-                        if trailing.is_complete() {
-                            return Some(stanza);
-                        }
-
-                        trailing.reset();
+                Some(Some(c)) if c.is_printable() => {
+                    stanza.push(c);
+                    len += 1;
+                }
+                Some(_) => {
+                    if len >= 4 {
+                        return Some(stanza);
                     }
+
+                    stanza.clear();
+                    len = 0;
                 }
-                _ => continue,
-            };
+            }
         }
     }
 }
@@ -137,6 +413,189 @@ mod test {
         assert!(b'~'.is_printable());
     }
 
+    #[test]
+    fn context_hint() {
+        let mut bytes = vec![b'\0'];
+        bytes.extend_from_slice(&[b'a'; 1000]);
+        bytes.extend_from_slice(b"\0foo\0foobar\0");
+        let mut bytes = std::io::Cursor::new(bytes)
+            .into_strings_iter()
+            .with_context_hint(6, 300);
+
+        for _ in 0..3 {
+            assert_eq!(Some("a".repeat(300)), bytes.next());
+        }
+        assert_eq!(Some("a".repeat(100)), bytes.next());
+        assert_eq!(Some("foobar".to_string()), bytes.next());
+        assert_eq!(None, bytes.next());
+    }
+
+    #[test]
+    fn max_length() {
+        let mut bytes = vec![b'\0'];
+        bytes.extend_from_slice(&[b'a'; 1000]);
+        bytes.extend_from_slice(b"\0foobar\0foo");
+        for (max_length, expected) in [
+            (None, vec!["a".repeat(1000), "foobar".to_string()]),
+            (Some(300), vec!["a".repeat(300), "foobar".to_string()]),
+            (Some(4), vec!["aaaa".to_string(), "foob".to_string()]),
+        ] {
+            let strings: Vec<String> = bytes
+                .as_slice()
+                .into_strings_iter()
+                .with_max_length(max_length)
+                .collect();
+            assert_eq!(strings, expected, "max_length {:?}", max_length);
+        }
+    }
+
+    #[test]
+    fn min_length() {
+        let bytes = b"\0a\0ab\0abcd\0abcdefghijklmnop\0abcdefghijklmno";
+        for (min_length, expected) in [
+            (
+                1,
+                &["a", "ab", "abcd", "abcdefghijklmnop", "abcdefghijklmno"][..],
+            ),
+            (4, &["abcd", "abcdefghijklmnop", "abcdefghijklmno"][..]),
+            (16, &["abcdefghijklmnop"][..]),
+        ] {
+            let strings: Vec<String> =
+                Strings::with_config(&bytes[..], StringsConfig { min_length }).collect();
+            assert_eq!(strings, expected, "min_length {}", min_length);
+        }
+    }
+
+    #[test]
+    fn long_trailing() {
+        let mut bytes = b"\0version\0versions\x01v1.2.3\0version 1.2.3"
+            .into_strings_iter()
+            .with_context_hint(8, usize::MAX);
+
+        assert_eq!(Some("versions".to_string()), bytes.next());
+        assert_eq!(Some("version 1.2.3".to_string()), bytes.next());
+        assert_eq!(None, bytes.next());
+    }
+
+    #[test]
+    fn with_offset() {
+        let bytes = b"\0\tfoobar\r\tbar\0barfoo\0\0xyz";
+        let strings: Vec<(u64, String)> = bytes[..].strings_with_offset(4).collect();
+        assert_eq!(
+            strings,
+            [(2, "foobar".to_string()), (14, "barfoo".to_string())]
+        );
+
+        let strings: Vec<(u64, String)> = bytes[..].strings_with_offset(1).collect();
+        assert_eq!(
+            strings,
+            [
+                (2, "foobar".to_string()),
+                (10, "bar".to_string()),
+                (14, "barfoo".to_string()),
+                (22, "xyz".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn printable_latin1() {
+        assert!(!ExtendedLatin1(b'\0').is_printable());
+        assert!(!ExtendedLatin1(b'\n').is_printable());
+        assert!(!ExtendedLatin1(0x7f).is_printable());
+        assert!(!ExtendedLatin1(0x85).is_printable());
+        assert!(ExtendedLatin1(b'a').is_printable());
+        assert!(ExtendedLatin1(0xa0).is_printable());
+        assert!(ExtendedLatin1(0xa9).is_printable());
+        assert!(ExtendedLatin1(0xff).is_printable());
+        assert!(!0xa9.is_printable());
+    }
+
+    #[test]
+    fn extended_charset() {
+        // Latin-1 characters at the start and the end of the strings
+        let bytes = b"\0\xa9 2021 ACME\0versi\xf3n 1.2.3\0\x85v1.0\xe9\0\xe9t\xe9\0";
+        let strings: Vec<String> = Strings::with_extended_charset(&bytes[..]).collect();
+        assert_eq!(strings, ["© 2021 ACME", "versión 1.2.3", "v1.0é"]);
+
+        // The default iterator keeps only accepting ASCII
+        let strings: Vec<String> = bytes.into_strings_iter().collect();
+        assert_eq!(strings, [" 2021 ACME", "versi", "n 1.2.3", "v1.0"]);
+    }
+
+    #[tokio::test]
+    async fn version_at_offset() {
+        use tokio::{fs::File, io::BufReader};
+
+        let mut file = BufReader::new(File::open("tests/fixtures/uboot/arm-spl").await.unwrap());
+        for (offset, max_len, v) in [
+            // "U-Boot 2017.11+fslc+ga07698f (Sep 10 2018 - 21:06:01 +0000)"
+            (0x075A, 21, Some("2017.11+fslc+ga07698f")),
+            (
+                0x075A,
+                0x100,
+                Some("2017.11+fslc+ga07698f (Sep 10 2018 - 21:06:01 +0000)"),
+            ),
+            // Leading new line and NUL bytes are skipped
+            (0x0751, 16, Some("U-Boot 2017.11")),
+            (0x0751, 2, None),
+            (0x10_0000, 16, None),
+        ] {
+            assert_eq!(
+                crate::version_at_offset(&mut file, offset, max_len)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+                "{:#x} ({} bytes)",
+                offset,
+                max_len
+            );
+        }
+    }
+
+    #[test]
+    fn printable_char() {
+        assert!(!'\0'.is_printable());
+        assert!(!'\n'.is_printable());
+        assert!(!'\u{85}'.is_printable());
+        assert!('a'.is_printable());
+        assert!('ó'.is_printable());
+        assert!('版'.is_printable());
+    }
+
+    #[test]
+    fn utf8_iterator() {
+        // Split the 'ó' encoding among two reads
+        let tail = [&b"\xb3n 1.2.3\0"[..], "版本号码\0x".as_bytes()].concat();
+        let bytes = b"\0\xffversi\xc3".chain(&tail[..]);
+        let mut bytes = bytes.into_utf8_strings_iter();
+
+        assert_eq!(Some("versión 1.2.3".to_string()), bytes.next());
+        assert_eq!(Some("版本号码".to_string()), bytes.next());
+        assert_eq!(None, bytes.next());
+    }
+
+    #[test]
+    fn peek() {
+        let bytes = b"\0\tfoobar\r\tbarfoo\0xyz";
+        let mut bytes = bytes[..].into_strings_iter();
+
+        assert_eq!(Some("foobar"), bytes.peek());
+        assert_eq!(Some("foobar"), bytes.peek());
+        assert_eq!(Some("foobar".to_string()), bytes.next());
+        assert_eq!(Some("barfoo".to_string()), bytes.next());
+        assert_eq!(None, bytes.peek());
+        assert_eq!(None, bytes.next());
+
+        // The offset is the one of the string yielded, even once it was
+        // looked at
+        let mut bytes = StringsWithOffset(b"\0\tfoobar\r\tbarfoo"[..].into_strings_iter());
+        assert_eq!(Some("foobar"), bytes.0.peek());
+        assert_eq!(Some((2, "foobar".to_string())), bytes.next());
+        assert_eq!(Some("barfoo"), bytes.0.peek());
+        assert_eq!(Some((10, "barfoo".to_string())), bytes.next());
+    }
+
     #[test]
     fn iterator() {
         let bytes = std::io::Cursor::new(b"\0\tfoobar\r\tbarfoo");
@@ -2,54 +2,188 @@
 //
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::VersionFinder;
+use crate::{
+    builder::Config, custom::Custom, fit::FDT_MAGIC, uimage::UIMAGE_MAGIC_NUMBER, FindVersionError,
+    VersionFinder,
+};
 use regex::bytes::Regex;
-use std::str;
+use std::{fmt, str};
 use tokio::io::{AsyncRead, AsyncReadExt};
 
-pub(crate) struct UBoot<'a, R: AsyncRead + Unpin> {
+// Size of each read, unless configured otherwise
+const UBOOT_BUFFER_SIZE: usize = 0x400;
+
+// Amount of bytes kept among reads, which must be longer than the longest
+// version string
+const UBOOT_OVERLAP: usize = 0x100;
+
+// Size of the largest SPL, which runs from the on-chip memory
+const SPL_MAX_SIZE: usize = 0x40000;
+
+// U-Boot version string, as printed in the boot banner
+pub(crate) const UBOOT_VERSION_PATTERN: &str =
+    r"U-Boot(?P<spl> SPL)? (?P<version>\d+.?\.[^\s]+) \(.*\)";
+
+/// U-Boot binary variant, as a split build provides both of them.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UBootVariant {
+    /// Secondary Program Loader (SPL), which loads the main image.
+    SPL,
+    /// Main U-Boot image.
+    Main,
+}
+
+/// Finder of the version printed in the U-Boot boot banner, as used by
+/// [`BinaryKind::UBoot`](crate::BinaryKind::UBoot).
+pub struct UBoot<'a, R: AsyncRead + Unpin> {
     buf: &'a mut R,
+    config: Config<'a>,
 }
 
 impl<'a, R: AsyncRead + Unpin> UBoot<'a, R> {
-    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
-        UBoot { buf }
+    /// Create the finder looking for the banner in the reader.
+    pub fn from_reader(buf: &'a mut R) -> Self {
+        UBoot {
+            buf,
+            config: Config::default(),
+        }
     }
-}
 
-#[async_trait::async_trait(?Send)]
-impl<'a, R: AsyncRead + Unpin> VersionFinder for UBoot<'a, R> {
-    async fn get_version(&mut self) -> Option<String> {
+    pub(crate) fn with_config(self, config: Config<'a>) -> Self {
+        UBoot { config, ..self }
+    }
+
+    /// Read the version from the U-Boot banner, along with the variant it
+    /// belongs to.
+    ///
+    /// Older SPLs print the same banner as the main image, with no SPL
+    /// marker, so their variant is told by the binary instead: the main image
+    /// is either packaged to be loaded by the SPL (as a uImage or a FIT
+    /// image) or bigger than any SPL, which must fit the on-chip memory it
+    /// runs from.
+    pub(crate) async fn get_version_variant(
+        &mut self,
+    ) -> Result<Option<(String, UBootVariant)>, FindVersionError> {
+        let banner = match self.read_banner().await? {
+            Some(banner) => banner,
+            None => return Ok(None),
+        };
+
+        let variant = if banner.spl {
+            UBootVariant::SPL
+        } else if banner.magic == Some(UIMAGE_MAGIC_NUMBER) || banner.magic == Some(FDT_MAGIC) {
+            UBootVariant::Main
+        } else {
+            // Only read on until the binary is known to be bigger than an SPL
+            let mut chunk = [0; UBOOT_BUFFER_SIZE];
+            let mut size = banner.read;
+            loop {
+                if size > SPL_MAX_SIZE {
+                    break UBootVariant::Main;
+                }
+                match self.buf.read(&mut chunk).await? {
+                    0 => break UBootVariant::SPL,
+                    n => size += n,
+                }
+            }
+        };
+
+        Ok(Some((banner.version, variant)))
+    }
+
+    /// Read the U-Boot banner.
+    async fn read_banner(&mut self) -> Result<Option<Banner>, FindVersionError> {
         // We use a fixed size buffer to avoid allocing too much memory on
         // embedded devices.
-        let mut buffer = [0; 0x200];
+        let size = self.config.buffer_size.unwrap_or(UBOOT_BUFFER_SIZE);
+        let overlap = UBOOT_OVERLAP.min(size / 2);
+        let mut buffer = vec![0; size];
+        let mut len = 0;
+        let mut read = 0;
+        let mut magic = None;
 
         // Avoid recompiling the pattern.
-        let re = Regex::new(r"U-Boot(?: SPL)? (?P<version>\d+.?\.[^\s]+) \(.*\)").unwrap();
+        let re = Regex::new(UBOOT_VERSION_PATTERN).unwrap();
 
         // Read the U-Boot version from the reader.
         loop {
             // If no more bytes are available, we need to return as we don't
             // have more content to read.
-            let n = self.buf.read(&mut buffer).await.ok()?;
+            let n = self.buf.read(&mut buffer[len..]).await?;
+            debug!("read {} bytes looking for the U-Boot version", n);
             if n == 0 {
-                return None;
+                return Ok(None);
+            }
+            len += n;
+            read += n;
+
+            // The start of the binary is still in the buffer until its tail
+            // is first kept for the next read
+            if magic.is_none() && read == len && len >= 4 {
+                magic = Some(u32::from_be_bytes([
+                    buffer[0], buffer[1], buffer[2], buffer[3],
+                ]));
             }
 
-            if let Some(version) = re
-                .captures(&buffer)
-                .and_then(|m| m.name("version"))
-                .and_then(|v| str::from_utf8(v.as_bytes()).ok())
-                .map(|v| v.to_string())
-            {
-                // Version pattern has been found, so we need to return the
-                // version.
-                return Some(version);
+            if let Some(captures) = re.captures(&buffer[..len]) {
+                if let Some(version) = captures
+                    .name("version")
+                    .and_then(|v| str::from_utf8(v.as_bytes()).ok())
+                {
+                    // Version pattern has been found, so we need to return
+                    // the version.
+                    debug!("matched the U-Boot version {}", version);
+                    return Ok(Some(Banner {
+                        version: version.to_string(),
+                        spl: captures.name("spl").is_some(),
+                        magic,
+                        read,
+                    }));
+                }
+            }
+
+            // Keep the tail of the buffer for the next read, so a version
+            // string spanning two reads is still found.
+            if len > overlap {
+                buffer.copy_within(len - overlap..len, 0);
+                len = overlap;
             }
         }
     }
 }
 
+/// U-Boot banner, along with what is known of the binary holding it.
+struct Banner {
+    version: String,
+    // Whether the banner is the one of an SPL
+    spl: bool,
+    // First bytes of the binary, big-endian
+    magic: Option<u32>,
+    // Amount of bytes read up to the banner
+    read: usize,
+}
+
+impl<'a, R: AsyncRead + Unpin> fmt::Debug for UBoot<'a, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UBoot").finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for UBoot<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        if let Some(pattern) = self.config.uboot_pattern {
+            return Custom::from_reader(self.buf, pattern)
+                .with_config(self.config)
+                .get_version()
+                .await;
+        }
+
+        Ok(self.read_banner().await?.map(|banner| banner.version))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::{version, BinaryKind};
@@ -72,9 +206,143 @@ mod test {
             ("arm-u-boot-dtb.img", "2019.04-00014-gc93ced78db"),
         ] {
             assert_eq!(
-                version(&mut fixture(f).await, BinaryKind::UBoot).await,
+                version(&mut fixture(f).await, BinaryKind::UBoot)
+                    .await
+                    .unwrap(),
                 Some(v.to_string()),
             );
         }
+
+        let image = crate::helpers::make_uboot_image("2023.01-rc4");
+        assert_eq!(
+            version(&mut std::io::Cursor::new(image), BinaryKind::UBoot)
+                .await
+                .unwrap(),
+            Some("2023.01-rc4".to_string()),
+        );
+    }
+
+    #[tokio::test]
+    async fn variant() {
+        use crate::{version_uboot_variant, UBootVariant};
+
+        // The arm-spl banner has no SPL marker, so it is told by being a
+        // raw binary small enough to be an SPL, while the main image is a
+        // uImage
+        for (f, v) in [
+            ("arm-spl", ("2017.11+fslc+ga07698f", UBootVariant::SPL)),
+            (
+                "arm-u-boot-dtb.img",
+                ("2019.04-00014-gc93ced78db", UBootVariant::Main),
+            ),
+        ] {
+            assert_eq!(
+                version_uboot_variant(&mut fixture(f).await).await.unwrap(),
+                Some((v.0.to_string(), v.1)),
+            );
+        }
+
+        let mut data = vec![0; 0x80];
+        data.extend_from_slice(b"\nU-Boot SPL 2023.10-rc3 (Oct 01 2023 - 10:00:00 +0000)\n\0");
+        assert_eq!(
+            version_uboot_variant(&mut std::io::Cursor::new(data))
+                .await
+                .unwrap(),
+            Some(("2023.10-rc3".to_string(), UBootVariant::SPL)),
+        );
+
+        // A raw main image is bigger than an SPL
+        let mut image = crate::helpers::make_uboot_image("2023.01-rc4");
+        image.resize(2 * super::SPL_MAX_SIZE, 0);
+        assert_eq!(
+            version_uboot_variant(&mut std::io::Cursor::new(image))
+                .await
+                .unwrap(),
+            Some(("2023.01-rc4".to_string(), UBootVariant::Main)),
+        );
+
+        assert_eq!(
+            version_uboot_variant(&mut std::io::Cursor::new(&[0; 0x100]))
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn from_bytes() {
+        use crate::version_from_bytes;
+
+        for (data, v) in [
+            (
+                &include_bytes!("../tests/fixtures/uboot/arm-spl")[..],
+                "2017.11+fslc+ga07698f",
+            ),
+            (
+                &include_bytes!("../tests/fixtures/uboot/arm-u-boot-dtb.img")[..],
+                "2019.04-00014-gc93ced78db",
+            ),
+        ] {
+            assert_eq!(
+                version_from_bytes(data, BinaryKind::UBoot).await.unwrap(),
+                Some(v.to_string()),
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn from_path() {
+        use crate::{version_from_path, FindVersionError};
+        use std::path::Path;
+
+        assert_eq!(
+            version_from_path(Path::new("tests/fixtures/uboot/arm-spl"), BinaryKind::UBoot)
+                .await
+                .unwrap(),
+            Some("2017.11+fslc+ga07698f".to_string()),
+        );
+        assert!(matches!(
+            version_from_path(Path::new("tests/fixtures/uboot/missing"), BinaryKind::UBoot).await,
+            Err(FindVersionError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound
+        ));
+    }
+
+    #[tokio::test]
+    async fn all() {
+        use crate::version_all;
+
+        let mut data = std::fs::read("tests/fixtures/uboot/arm-spl").unwrap();
+        data.extend(std::fs::read("tests/fixtures/uboot/arm-u-boot-dtb.img").unwrap());
+        data.extend(std::fs::read("tests/fixtures/uboot/arm-spl").unwrap());
+
+        assert_eq!(
+            version_all(&mut std::io::Cursor::new(data), BinaryKind::UBoot)
+                .await
+                .unwrap(),
+            vec![
+                "2017.11+fslc+ga07698f".to_string(),
+                "2019.04-00014-gc93ced78db".to_string()
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn spanning_reads() {
+        use crate::{uboot::UBoot, VersionFinder};
+        use tokio::io::AsyncReadExt;
+
+        // Place the version string across the boundary of the first read
+        let mut data = vec![0; 0x1FE];
+        data.extend_from_slice(b"U-Boot 2023.10-rc3 (Oct 01 2023 - 10:00:00 +0000)\0");
+        data.extend_from_slice(&[0; 0x400]);
+        let (first, second) = data.split_at(0x200);
+
+        assert_eq!(
+            UBoot::from_reader(&mut first.chain(second))
+                .get_version()
+                .await
+                .unwrap(),
+            Some("2023.10-rc3".to_string()),
+        );
     }
 }
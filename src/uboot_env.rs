@@ -0,0 +1,200 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{uboot::UBOOT_VERSION_PATTERN, FindVersionError, VersionFinder};
+use regex::Regex;
+use std::str;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+// Size of the CRC32 checksum heading the environment
+const ENV_CRC_SIZE: usize = 4;
+
+/// Environment data of a single copy, along with its flags, once its CRC32
+/// checksum is verified.
+fn env_data(copy: &[u8], redundant: bool) -> Option<(u8, &[u8])> {
+    // Taken from: https://source.denx.de/u-boot/u-boot/-/blob/master/include/env_internal.h
+    //
+    // Offset  Size  Name   Meaning
+    // 0000    4     crc    CRC32 of the data, little-endian
+    // 0004    1     flags  Copy counter, only for redundant environments
+    // ....    ...   data   NUL separated key=value pairs, ended by an empty one
+    let start = if redundant {
+        ENV_CRC_SIZE + 1
+    } else {
+        ENV_CRC_SIZE
+    };
+    let data = copy.get(start..)?;
+    let crc = u32::from_le_bytes(copy[..ENV_CRC_SIZE].try_into().ok()?);
+    if crc32fast::hash(data) != crc {
+        debug!("U-Boot environment CRC32 {:#010x} mismatch", crc);
+        return None;
+    }
+
+    let flags = if redundant { copy[ENV_CRC_SIZE] } else { 0 };
+    Some((flags, data))
+}
+
+/// Pick the copy written last, as done by U-Boot: the one with the higher
+/// flags counter, unless it wrapped around.
+fn current_copy<'a>(
+    first: Option<(u8, &'a [u8])>,
+    second: Option<(u8, &'a [u8])>,
+) -> Option<&'a [u8]> {
+    match (first, second) {
+        (Some((0xFF, _)), Some((0, data))) => Some(data),
+        (Some((0, data)), Some((0xFF, _))) => Some(data),
+        (Some((flags1, data1)), Some((flags2, data2))) => {
+            Some(if flags2 > flags1 { data2 } else { data1 })
+        }
+        (first, second) => first.or(second).map(|(_, data)| data),
+    }
+}
+
+/// Get the `ver` variable of the environment, using the version of the
+/// U-Boot banner it holds if any.
+fn find_version(data: &[u8]) -> Option<String> {
+    let ver = data
+        .split(|&b| b == 0)
+        .take_while(|pair| !pair.is_empty())
+        .find_map(|pair| pair.strip_prefix(b"ver="))
+        .and_then(|v| str::from_utf8(v).ok())?;
+    debug!("read U-Boot environment ver={:?}", ver);
+
+    let re = Regex::new(UBOOT_VERSION_PATTERN).unwrap();
+    Some(
+        re.captures(ver)
+            .and_then(|c| c.name("version"))
+            .map_or(ver, |v| v.as_str())
+            .to_string(),
+    )
+}
+
+pub(crate) struct UBootEnv<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+    redundant: bool,
+}
+
+impl<'a, R: AsyncRead + Unpin> UBootEnv<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        UBootEnv {
+            buf,
+            redundant: false,
+        }
+    }
+
+    /// Read a redundant environment, holding two copies of the same size.
+    pub(crate) fn redundant(self) -> Self {
+        UBootEnv {
+            redundant: true,
+            ..self
+        }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for UBootEnv<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        // The environment fills the whole partition, whose size is the one
+        // the checksum is computed for.
+        let mut env = Vec::new();
+        self.buf.read_to_end(&mut env).await?;
+
+        let data = if self.redundant {
+            if env.len() % 2 != 0 {
+                return Ok(None);
+            }
+            let (first, second) = env.split_at(env.len() / 2);
+            current_copy(env_data(first, true), env_data(second, true))
+        } else {
+            env_data(&env, false).map(|(_, data)| data)
+        };
+
+        Ok(data.and_then(find_version))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, version_uboot_env_redundant, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/ubootenv/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("env.bin", Some("2022.04")),
+            ("env-redundant.bin", None),
+            ("../uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::UBootEnv)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+                "{} version mismatch",
+                f
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn redundant() {
+        // The second copy was written last
+        assert_eq!(
+            version_uboot_env_redundant(&mut fixture("env-redundant.bin").await)
+                .await
+                .unwrap(),
+            Some("2022.04".to_string())
+        );
+
+        // Only the first copy is valid
+        let mut data = std::fs::read("tests/fixtures/ubootenv/env-redundant.bin").unwrap();
+        let second = data.len() / 2;
+        data[second + 0x10] ^= 0xFF;
+        assert_eq!(
+            version_uboot_env_redundant(&mut std::io::Cursor::new(&data))
+                .await
+                .unwrap(),
+            Some("2021.10".to_string())
+        );
+
+        // The flags counter wrapped around, so the first copy is the last
+        // written one
+        let mut data = std::fs::read("tests/fixtures/ubootenv/env-redundant.bin").unwrap();
+        data[4] = 0x00;
+        data[second + 4] = 0xFF;
+        for copy in [0, second] {
+            let crc = crc32fast::hash(&data[copy + 5..copy + second]);
+            data[copy..copy + 4].copy_from_slice(&crc.to_le_bytes());
+        }
+        assert_eq!(
+            version_uboot_env_redundant(&mut std::io::Cursor::new(&data))
+                .await
+                .unwrap(),
+            Some("2021.10".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn corrupted() {
+        let mut data = std::fs::read("tests/fixtures/ubootenv/env.bin").unwrap();
+        data[0x10] ^= 0xFF;
+
+        assert_eq!(
+            version(&mut std::io::Cursor::new(data), BinaryKind::UBootEnv)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+}
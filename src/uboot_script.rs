@@ -0,0 +1,104 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    strings::IntoStringsIter,
+    uimage::{read_uimage_header, UIMAGE_HEADER_SIZE},
+    FindVersionError, VersionFinder,
+};
+use regex::Regex;
+use std::io::{self, SeekFrom};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+// U-Boot Image type used by scripts (IH_TYPE_SCRIPT)
+pub(crate) const IH_TYPE_SCRIPT: u8 = 6;
+
+pub(crate) struct UBootScript<'a, R: AsyncRead + AsyncSeek + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> UBootScript<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        UBootScript { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> VersionFinder for UBootScript<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        let header = match read_uimage_header(self.buf).await {
+            Ok(header) => header,
+            // Too short to hold a uImage header, so it is a raw text script
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut script = Vec::new();
+        match header {
+            // The script is wrapped in a uImage container (e.g: boot.scr), so
+            // only its payload is used.
+            Some(header) => {
+                self.buf
+                    .seek(SeekFrom::Start(UIMAGE_HEADER_SIZE as u64))
+                    .await?;
+                let mut payload = (&mut *self.buf).take(header.data_size.into());
+
+                // Script images use the multi-file layout, so the payload
+                // starts with a zero terminated list of the file sizes.
+                if header.image_type == IH_TYPE_SCRIPT {
+                    while payload.read_u32().await? != 0 {}
+                }
+
+                payload.read_to_end(&mut script).await?;
+            }
+
+            // Raw text script
+            None => {
+                self.buf.seek(SeekFrom::Start(0)).await?;
+                self.buf.read_to_end(&mut script).await?;
+            }
+        }
+
+        let re = Regex::new(r"setenv\s+(?:boot)?version\s+(?P<version>[0-9][^\s]+)").unwrap();
+        for line in script.into_strings_iter() {
+            if let Some(v) = re.captures(&line).and_then(|c| c.name("version")) {
+                return Ok(Some(v.as_str().to_string()));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("ubootscript/boot.scr", Some("2019.04-r3")),
+            ("ubootscript/boot.txt", Some("1.4.0")),
+            ("uboot/arm-u-boot-dtb.img", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::UBootScript)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+}
@@ -0,0 +1,189 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{efi_capsule::read_capsule_header, FindVersionError, VersionFinder};
+use std::io::SeekFrom;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+// EFI_FIRMWARE_MANAGEMENT_CAPSULE_ID_GUID
+// {6DCBD5ED-E82D-4C44-BDA1-7194199AD92A}, as stored in the file (the first
+// three fields are little-endian)
+pub(crate) const FMP_CAPSULE_GUID: [u8; 16] = [
+    0xed, 0xd5, 0xcb, 0x6d, 0x2d, 0xe8, 0x44, 0x4c, 0xbd, 0xa1, 0x71, 0x94, 0x19, 0x9a, 0xd9, 0x2a,
+];
+
+// FMP payload header signature ("MSS1"), little-endian
+const FMP_PAYLOAD_HEADER_SIGNATURE: u32 = 0x3153_534D;
+
+// Size of the monotonic count preceding the certificate of authenticated
+// images (EFI_FIRMWARE_IMAGE_AUTHENTICATION)
+const MONOTONIC_COUNT_SIZE: u64 = 8;
+
+/// Size of the EFI_FIRMWARE_MANAGEMENT_CAPSULE_IMAGE_HEADER, which grew with
+/// each of its versions.
+fn image_header_size(version: u32) -> u64 {
+    match version {
+        1 => 0x20,
+        2 => 0x28,
+        _ => 0x30,
+    }
+}
+
+pub(crate) struct UefiCapsule<'a, R: AsyncRead + AsyncSeek + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> UefiCapsule<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        UefiCapsule { buf }
+    }
+
+    /// Read the firmware version of the FMP payload header at the given
+    /// offset, if any.
+    async fn read_payload_version(&mut self, offset: u64) -> Result<Option<u32>, FindVersionError> {
+        // Taken from: edk2 FmpDevicePkg/Include/Library/FmpPayloadHeaderLib.h
+        //
+        // Offset  Size  Name                    Meaning
+        // 0000    4     Signature               "MSS1"
+        // 0004    4     HeaderSize              Size of the payload header
+        // 0008    4     FwVersion               Firmware version, as reported by the ESRT
+        // 000C    4     LowestSupportedVersion  Lowest version allowed to be installed
+        self.buf.seek(SeekFrom::Start(offset)).await?;
+        if self.buf.read_u32_le().await? != FMP_PAYLOAD_HEADER_SIGNATURE {
+            return Ok(None);
+        }
+
+        let _header_size = self.buf.read_u32_le().await?;
+        Ok(Some(self.buf.read_u32_le().await?))
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> VersionFinder for UefiCapsule<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        let header = match read_capsule_header(self.buf, &FMP_CAPSULE_GUID).await? {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+
+        // Taken from: UEFI Specification, EFI_FIRMWARE_MANAGEMENT_CAPSULE_HEADER
+        //
+        // Offset  Size  Name                 Meaning
+        // 0000    4     Version              Version of the header
+        // 0004    2     EmbeddedDriverCount  Number of drivers in the capsule
+        // 0006    2     PayloadItemCount     Number of firmware images in the capsule
+        // 0008    ...   ItemOffsetList       Offsets of the drivers, then of the images
+        let start = u64::from(header.header_size);
+        self.buf.seek(SeekFrom::Start(start)).await?;
+        let _version = self.buf.read_u32_le().await?;
+        let drivers = self.buf.read_u16_le().await?;
+        let payloads = self.buf.read_u16_le().await?;
+        if payloads == 0 {
+            return Ok(None);
+        }
+
+        // Only the first firmware image is looked at
+        self.buf
+            .seek(SeekFrom::Current(8 * i64::from(drivers)))
+            .await?;
+        // The offsets are not trusted, as the capsule may be malformed
+        let image = match start.checked_add(self.buf.read_u64_le().await?) {
+            Some(image) => image,
+            None => return Ok(None),
+        };
+
+        // Taken from: UEFI Specification, EFI_FIRMWARE_MANAGEMENT_CAPSULE_IMAGE_HEADER
+        //
+        // Offset  Size  Name                    Meaning
+        // 0000    4     Version                 Version of the header
+        // 0004    16    UpdateImageTypeId       GUID of the device firmware
+        // 0014    1     UpdateImageIndex        Index of the image in the device
+        // 0015    3     reserved_bytes
+        // 0018    4     UpdateImageSize         Size of the image
+        // 001C    4     UpdateVendorCodeSize    Size of the vendor code following the image
+        // 0020    8     UpdateHardwareInstance  Hardware instance (version 2 onward)
+        // 0028    8     ImageCapsuleSupport     Supported capsule types (version 3 onward)
+        self.buf.seek(SeekFrom::Start(image)).await?;
+        let payload = match image.checked_add(image_header_size(self.buf.read_u32_le().await?)) {
+            Some(payload) => payload,
+            None => return Ok(None),
+        };
+
+        // Images may be signed, having the authentication information
+        // (EFI_FIRMWARE_IMAGE_AUTHENTICATION) before the payload header. It
+        // holds the monotonic count followed by a WIN_CERTIFICATE, whose
+        // first field is its length.
+        let version = match self.read_payload_version(payload).await? {
+            Some(version) => version,
+            None => {
+                let cert = match payload.checked_add(MONOTONIC_COUNT_SIZE) {
+                    Some(cert) => cert,
+                    None => return Ok(None),
+                };
+                self.buf.seek(SeekFrom::Start(cert)).await?;
+                let cert_length = self.buf.read_u32_le().await?;
+                let signed_payload = match cert.checked_add(u64::from(cert_length)) {
+                    Some(signed_payload) => signed_payload,
+                    None => return Ok(None),
+                };
+                match self.read_payload_version(signed_payload).await? {
+                    Some(version) => version,
+                    None => return Ok(None),
+                }
+            }
+        };
+        debug!("read FMP payload firmware version {:#x}", version);
+
+        Ok(Some(format!("{}.{}", version >> 16, version & 0xFFFF)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("efi/fmp-capsule.bin", Some("2.5")),
+            ("efi/fmp-capsule-signed.bin", Some("1.10")),
+            ("efi/capsule.bin", None),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::UEFICapsule)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+                "{} version mismatch",
+                f
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn overflowing_offset() {
+        // The offset of the firmware image, in the ItemOffsetList, overflows
+        // when added to the header size
+        let mut data = std::fs::read("tests/fixtures/efi/fmp-capsule.bin").unwrap();
+        data[0x28..0x30].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(
+            version(&mut std::io::Cursor::new(data), BinaryKind::UEFICapsule)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+}
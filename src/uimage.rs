@@ -0,0 +1,238 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{FindVersionError, VersionFinder};
+use std::io::{self, SeekFrom};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+// U-Boot Image Magic Number
+pub(crate) const UIMAGE_MAGIC_NUMBER: u32 = 0x2705_1956;
+
+// U-Boot Image header size
+pub(crate) const UIMAGE_HEADER_SIZE: usize = 0x40;
+
+/// Legacy U-Boot image (uImage) header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UImageHeader {
+    /// Image header magic number.
+    pub magic: u32,
+    /// Image header CRC32 checksum.
+    pub header_crc: u32,
+    /// Image creation timestamp, in seconds since the Unix epoch.
+    pub timestamp: u32,
+    /// Image data size.
+    pub data_size: u32,
+    /// Data load address.
+    pub load_addr: u32,
+    /// Entry point address.
+    pub entry_point: u32,
+    /// Image data CRC32 checksum.
+    pub data_crc: u32,
+    /// Operating system.
+    pub os: u8,
+    /// CPU architecture.
+    pub arch: u8,
+    /// Image type.
+    pub image_type: u8,
+    /// Compression type.
+    pub compression: u8,
+    /// Image name, without the NUL padding.
+    pub name: String,
+}
+
+impl UImageHeader {
+    fn from_bytes(header: &[u8; UIMAGE_HEADER_SIZE]) -> Option<Self> {
+        let u32_at =
+            |offset: usize| u32::from_be_bytes(header[offset..offset + 4].try_into().unwrap());
+
+        // Taken from: https://source.denx.de/u-boot/u-boot/-/blob/master/include/image.h
+        //
+        // Offset  Size  Name      Meaning
+        // 0000    4     ih_magic  Image Header Magic Number
+        // 0004    4     ih_hcrc   Image Header CRC Checksum
+        // 0008    4     ih_time   Image Creation Timestamp
+        // 000C    4     ih_size   Image Data Size
+        // 0010    4     ih_load   Data Load Address
+        // 0014    4     ih_ep     Entry Point Address
+        // 0018    4     ih_dcrc   Image Data CRC Checksum
+        // 001C    1     ih_os     Operating System
+        // 001D    1     ih_arch   CPU architecture
+        // 001E    1     ih_type   Image Type
+        // 001F    1     ih_comp   Compression Type
+        // 0020    32    ih_name   Image Name
+        let magic = u32_at(0x00);
+        if magic != UIMAGE_MAGIC_NUMBER {
+            return None;
+        }
+
+        // The header checksum is computed with the ih_hcrc field zeroed
+        let header_crc = u32_at(0x04);
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&header[..0x04]);
+        hasher.update(&[0; 4]);
+        hasher.update(&header[0x08..]);
+        if hasher.finalize() != header_crc {
+            return None;
+        }
+
+        let name = &header[0x20..];
+        let name = &name[..name.iter().position(|&b| b == 0).unwrap_or(name.len())];
+
+        Some(UImageHeader {
+            magic,
+            header_crc,
+            timestamp: u32_at(0x08),
+            data_size: u32_at(0x0C),
+            load_addr: u32_at(0x10),
+            entry_point: u32_at(0x14),
+            data_crc: u32_at(0x18),
+            os: header[0x1C],
+            arch: header[0x1D],
+            image_type: header[0x1E],
+            compression: header[0x1F],
+            name: String::from_utf8_lossy(name).into_owned(),
+        })
+    }
+
+    /// Image creation date, formatted as `YYYY-MM-DD`.
+    pub fn date(&self) -> String {
+        format_date(self.timestamp.into())
+    }
+}
+
+// Convert the seconds since the Unix epoch to a `YYYY-MM-DD` date.
+//
+// Taken from: http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+pub(crate) fn format_date(timestamp: u64) -> String {
+    let z = timestamp / 86400 + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+pub(crate) async fn read_uimage_header<R: AsyncRead + AsyncSeek + Unpin>(
+    buf: &mut R,
+) -> io::Result<Option<UImageHeader>> {
+    // U-Boot Image header is stored at begin of file
+    buf.seek(SeekFrom::Start(0)).await?;
+    let mut header = [0; UIMAGE_HEADER_SIZE];
+    buf.read_exact(&mut header).await?;
+
+    Ok(UImageHeader::from_bytes(&header))
+}
+
+pub(crate) struct UImage<'a, R: AsyncRead + AsyncSeek + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> UImage<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        UImage { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> VersionFinder for UImage<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        let header = match read_uimage_header(self.buf).await? {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+
+        // Use the image name as version, falling back to the image creation
+        // date when no name has been set.
+        if header.name.trim().is_empty() {
+            return Ok(Some(header.date()));
+        }
+
+        Ok(Some(header.name))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{uimage_header, version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("linuxkernel/arm-uImage", "Linux-4.1.15-1.2.0+g274a055"),
+            (
+                "uboot/arm-u-boot-dtb.img",
+                "U-Boot 2019.04-00014-gc93ced78db",
+            ),
+            ("uimage/arm-noname-uImage", "2016-10-10"),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::UImageHeader)
+                    .await
+                    .unwrap(),
+                Some(v.to_string()),
+            );
+        }
+
+        let image = crate::helpers::make_linux_uimage("6.1.55");
+        assert_eq!(
+            version(&mut std::io::Cursor::new(image), BinaryKind::UImageHeader)
+                .await
+                .unwrap(),
+            Some("Linux-6.1.55".to_string()),
+        );
+    }
+
+    #[tokio::test]
+    async fn header() {
+        let header = uimage_header(&mut fixture("linuxkernel/arm-uImage").await)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(header.timestamp, 0x57fb_71f7);
+        assert_eq!(header.data_size, 0x005f_8dd0);
+        assert_eq!(header.load_addr, 0x1000_8000);
+        assert_eq!(header.entry_point, 0x1000_8000);
+        assert_eq!((header.os, header.arch), (5, 2));
+        assert_eq!((header.image_type, header.compression), (2, 0));
+    }
+
+    #[tokio::test]
+    async fn invalid() {
+        let mut data = std::fs::read("tests/fixtures/linuxkernel/arm-uImage").unwrap();
+        data[0x20] = b'l';
+
+        assert_eq!(
+            version(&mut std::io::Cursor::new(data), BinaryKind::UImageHeader)
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            version(
+                &mut fixture("linuxkernel/x86-bzImage").await,
+                BinaryKind::UImageHeader
+            )
+            .await
+            .unwrap(),
+            None
+        );
+    }
+}
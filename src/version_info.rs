@@ -0,0 +1,260 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{strings::StringsExt, BinaryKind};
+use std::{fmt, io::SeekFrom};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+/// Version found for a binary, along with the binary kind used to find it.
+///
+/// When looking for the version with [`BinaryKind::Auto`], the kind is the
+/// one which provided the version (e.g: [`BinaryKind::UBoot`]).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VersionInfo {
+    /// Binary kind used to find the version.
+    pub kind: BinaryKind,
+    /// Version found.
+    pub version: String,
+    /// Offset of the version in the binary, unset when it is not stored as
+    /// is (e.g: in compressed payloads).
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub offset: Option<u64>,
+}
+
+impl fmt::Display for VersionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind, self.version)
+    }
+}
+
+/// Find the offset of the first occurrence of the version in the printable
+/// strings of the binary.
+pub(crate) async fn find_offset<R: AsyncRead + AsyncSeek + Unpin>(
+    buf: &mut R,
+    version: &str,
+) -> std::io::Result<Option<u64>> {
+    if version.is_empty() {
+        return Ok(None);
+    }
+
+    // FIXME: Avoid reading the whole file
+    buf.seek(SeekFrom::Start(0)).await?;
+    let mut data = Vec::new();
+    buf.read_to_end(&mut data).await?;
+
+    // The strings only hold ASCII characters, so their character and byte
+    // positions are the same
+    Ok(data[..]
+        .strings_with_offset(version.len())
+        .find_map(|(offset, s)| s.find(version).map(|n| offset + n as u64)))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{detect, version_info, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn auto() {
+        for (f, v) in &[
+            ("uboot/arm-spl", Some("u-boot: 2017.11+fslc+ga07698f")),
+            (
+                "linuxkernel/x86-bzImage",
+                Some("linux-kernel: 4.1.30-1-MANJARO"),
+            ),
+            ("helm/nginx-9.4.1.tgz", None),
+        ] {
+            assert_eq!(
+                version_info(&mut fixture(f).await, BinaryKind::Auto)
+                    .await
+                    .unwrap()
+                    .map(|i| i.to_string()),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn offset() {
+        for (f, kind, offset) in [
+            ("uboot/arm-spl", BinaryKind::Auto, Some(0x075A)),
+            ("xzpayload/vmlinux.xz", BinaryKind::XzPayload, None),
+        ] {
+            assert_eq!(
+                version_info(&mut fixture(f).await, kind)
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .offset,
+                offset,
+                "{} offset mismatch",
+                f
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn given_kind() {
+        let info = version_info(
+            &mut fixture("linuxkernel/arm64-Image").await,
+            BinaryKind::custom("kernel", r"Linux version (\S+)"),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(info.version, "5.10.0-9-arm64");
+        assert_eq!(info.to_string(), "kernel: 5.10.0-9-arm64");
+    }
+
+    #[tokio::test]
+    async fn detected() {
+        for (f, v) in &[
+            ("fit/u-boot.itb", Some("fit: 2022.04")),
+            (
+                "linuxkernel/arm64-Image",
+                Some("linux-kernel: 5.10.0-9-arm64"),
+            ),
+            ("uboot/arm-spl", Some("u-boot: 2017.11+fslc+ga07698f")),
+            ("helm/nginx-9.4.1.tgz", None),
+        ] {
+            assert_eq!(
+                detect(&mut fixture(f).await)
+                    .await
+                    .unwrap()
+                    .map(|i| i.to_string()),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn kind() {
+        for (kind, json) in [
+            (BinaryKind::UBoot, r#""u-boot""#),
+            (BinaryKind::LinuxKernel, r#""linux-kernel""#),
+            (BinaryKind::AndroidOtaPayload, r#""android-ota-payload""#),
+            (BinaryKind::FIT, r#""fit""#),
+            (BinaryKind::UEFICapsule, r#""uefi-capsule""#),
+            (
+                BinaryKind::custom("bootloader", r"v(\d+)"),
+                r#"{"custom":{"name":"bootloader","pattern":"v(\\d+)"}}"#,
+            ),
+        ] {
+            assert_eq!(serde_json::to_string(&kind).unwrap(), json);
+            assert_eq!(
+                format!("{:?}", serde_json::from_str::<BinaryKind>(json).unwrap()),
+                format!("{:?}", kind)
+            );
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn display() {
+        for kind in BinaryKind::iter().chain(std::iter::once(BinaryKind::Auto)) {
+            assert_eq!(
+                serde_json::to_string(&kind).unwrap(),
+                format!("\"{}\"", kind)
+            );
+            assert_eq!(
+                format!(
+                    "{:?}",
+                    kind.to_string().to_uppercase().parse::<BinaryKind>()
+                ),
+                format!("{:?}", Ok::<_, crate::UnknownBinaryKind>(kind))
+            );
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn iter_complete() {
+        // Deserializing an unknown name fails listing every variant, so the
+        // variants missing from BinaryKind::iter are caught
+        let error = serde_json::from_str::<BinaryKind>("\"unknown\"")
+            .unwrap_err()
+            .to_string();
+        let expected = error.split("expected one of ").nth(1).unwrap();
+        let names: Vec<_> = expected
+            .split(", ")
+            .map(|name| name.split('`').nth(1).unwrap())
+            .filter(|name| !["auto", "custom"].contains(name))
+            .collect();
+
+        assert_eq!(
+            BinaryKind::iter()
+                .map(|kind| kind.to_string())
+                .collect::<Vec<_>>(),
+            names
+        );
+    }
+
+    #[test]
+    fn description() {
+        for (kind, description, mime) in [
+            (
+                BinaryKind::UBoot,
+                "U-Boot bootloader",
+                "application/octet-stream",
+            ),
+            (
+                BinaryKind::LinuxKernel,
+                "Linux Kernel",
+                "application/octet-stream",
+            ),
+            (
+                BinaryKind::ElfComment,
+                "ELF binary",
+                "application/x-executable",
+            ),
+            (BinaryKind::PythonWheel, "Python wheel", "application/zip"),
+            (
+                BinaryKind::custom("bootloader", r"v(\d+)"),
+                "Custom pattern",
+                "application/octet-stream",
+            ),
+        ] {
+            assert_eq!(kind.description(), description);
+            assert_eq!(kind.mime_hint(), mime);
+        }
+
+        // Each kind has its own description
+        let descriptions: std::collections::HashSet<_> =
+            BinaryKind::iter().map(|kind| kind.description()).collect();
+        assert_eq!(descriptions.len(), BinaryKind::iter().count());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize() {
+        use super::VersionInfo;
+
+        let info = VersionInfo {
+            kind: BinaryKind::XzPayload,
+            version: "5.10.0-9-arm64".to_string(),
+            offset: None,
+        };
+        let json = serde_json::to_string(&info).unwrap();
+        assert_eq!(json, r#"{"kind":"xz-payload","version":"5.10.0-9-arm64"}"#);
+
+        let info: VersionInfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(format!("{:?}", info.kind), "XzPayload");
+        assert_eq!(info.version, "5.10.0-9-arm64");
+    }
+}
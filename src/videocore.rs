@@ -0,0 +1,108 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{FindVersionError, VersionFinder};
+use std::str;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+// Marker preceding the firmware version, following the build date (e.g:
+// "Dec  1 2023 12:00:00, version f3b6...")
+const VIDEOCORE_VERSION_MARKER: &[u8] = b", version ";
+
+// The version string is always found at the begin of the firmware
+const VIDEOCORE_SCAN_SIZE: u64 = 0x10000;
+
+pub(crate) struct VideocoreFirmware<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + Unpin> VideocoreFirmware<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        VideocoreFirmware { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for VideocoreFirmware<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        let mut data = Vec::new();
+        (&mut *self.buf)
+            .take(VIDEOCORE_SCAN_SIZE)
+            .read_to_end(&mut data)
+            .await?;
+
+        let start = match data
+            .windows(VIDEOCORE_VERSION_MARKER.len())
+            .position(|w| w == VIDEOCORE_VERSION_MARKER)
+        {
+            Some(position) => position + VIDEOCORE_VERSION_MARKER.len(),
+            None => return Ok(None),
+        };
+        debug!("found VideoCore version marker at offset {:#x}", start);
+
+        // The version is the hash of the firmware source revision
+        let len = data[start..]
+            .iter()
+            .take_while(|b| b.is_ascii_hexdigit())
+            .count();
+        Ok(match len {
+            0 => None,
+            _ => str::from_utf8(&data[start..start + len])
+                .ok()
+                .map(str::to_string),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            (
+                "videocore/start4.elf",
+                Some("30f0c5e4d076da3ab4f341d88e7d505760b93ad7"),
+            ),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::VideocoreFirmware)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+                "{} version mismatch",
+                f
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn beyond_scan_size() {
+        let mut data = vec![0; 0x10000];
+        data.extend_from_slice(b"Dec  1 2023 12:00:00, version 30f0c5e4 (release)\0");
+
+        assert_eq!(
+            version(
+                &mut std::io::Cursor::new(data),
+                BinaryKind::VideocoreFirmware
+            )
+            .await
+            .unwrap(),
+            None
+        );
+    }
+}
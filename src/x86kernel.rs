@@ -0,0 +1,175 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{FindVersionError, VersionFinder};
+use regex::bytes::Regex;
+use std::{
+    io::{self, SeekFrom},
+    str,
+};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+// Taken from: https://git.kernel.org/pub/scm/linux/kernel/git/torvalds/linux.git/tree/Documentation/x86/boot.txt#n144
+//
+// Offset  Proto   Name            Meaning
+// /Size
+// ...
+// 01F1/1  ALL(1   setup_sects     The size of the setup in sectors
+// ...
+// 01FE/2  ALL     boot_flag       0xAA55 magic number
+// ...
+// 0206/2  2.00+   version         Boot protocol version supported
+// ...
+// 020E/2  2.00+   kernel_version  Pointer to kernel version string
+// ...
+// 0236/2  2.12+   xloadflags      Boot protocol option flags
+
+// xloadflags bit set for kernels with a 64-bit entry point
+const XLF_KERNEL_64: u16 = 0x1;
+
+/// Verify the boot_flag magic number of the x86 boot protocol header.
+pub(crate) async fn has_boot_flag<R: AsyncRead + AsyncSeek + Unpin>(
+    buf: &mut R,
+) -> io::Result<bool> {
+    buf.seek(SeekFrom::Start(0x01FE)).await?;
+    Ok(buf.read_u16_le().await? == 0xAA55)
+}
+
+/// Tell whether the kernel is a 64-bit one, using the xloadflags of the x86
+/// boot protocol header.
+///
+/// Kernels older than the 2.12 boot protocol, which added the field, are
+/// reported as 32-bit ones.
+pub(crate) async fn is_64bit<R: AsyncRead + AsyncSeek + Unpin>(buf: &mut R) -> io::Result<bool> {
+    buf.seek(SeekFrom::Start(0x0206)).await?;
+    if buf.read_u16_le().await? < 0x020C {
+        return Ok(false);
+    }
+
+    buf.seek(SeekFrom::Start(0x0236)).await?;
+    Ok(buf.read_u16_le().await? & XLF_KERNEL_64 != 0)
+}
+
+/// Read the kernel version string pointed by the x86 boot protocol header.
+pub(crate) async fn read_version<R: AsyncRead + AsyncSeek + Unpin>(
+    buf: &mut R,
+) -> io::Result<Option<String>> {
+    // Get the setup_sects information
+    buf.seek(SeekFrom::Start(0x01F1)).await?;
+    let setup_sects = u64::from(buf.read_u8().await?);
+
+    // Get kernel_version pointer
+    buf.seek(SeekFrom::Start(0x020E)).await?;
+    let kernel_version_ptr = u64::from(buf.read_u16_le().await?);
+
+    // Field name:     kernel_version
+    // Type:           read
+    // Offset/size:    0x20e/2
+    // Protocol:       2.00+
+    //
+    //   If set to a nonzero value, contains a pointer to a NUL-terminated
+    //   human-readable kernel version number string, less 0x200.  This can
+    //   be used to display the kernel version to the user.  This value
+    //   should be less than (0x200*setup_sects).
+    if kernel_version_ptr >= setup_sects * 0x200 {
+        return Ok(None);
+    }
+
+    // Move to the kernel version location
+    buf.seek(SeekFrom::Start(kernel_version_ptr + 0x200))
+        .await?;
+
+    // Read the Linux kernel version from the reader
+    let mut buffer = [0; 0x200];
+    let _ = buf.read(&mut buffer).await?;
+
+    let re = Regex::new(r"(?P<version>\d+\.\d+[^\s\u{0}]*)").unwrap();
+    Ok(re
+        .captures(&buffer)
+        .and_then(|m| m.name("version"))
+        .and_then(|v| str::from_utf8(v.as_bytes()).ok())
+        .map(|v| v.to_string()))
+}
+
+pub(crate) struct X86Kernel<'a, R: AsyncRead + AsyncSeek + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> X86Kernel<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        X86Kernel { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> VersionFinder for X86Kernel<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        if !has_boot_flag(self.buf).await? {
+            return Ok(None);
+        }
+
+        Ok(read_version(self.buf).await?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/linuxkernel/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn x86_version() {
+        for (f, v) in &[
+            ("arm-uImage", None),
+            ("arm-zImage", None),
+            ("x86-bzImage", Some("4.1.30-1-MANJARO")),
+            ("x86-zImage", Some("4.1.30-1-MANJARO")),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::X86Kernel)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string())
+            );
+        }
+
+        let image = crate::helpers::make_linux_bzimage("6.1.0-13-amd64");
+        assert_eq!(
+            version(&mut std::io::Cursor::new(image), BinaryKind::X86Kernel)
+                .await
+                .unwrap(),
+            Some("6.1.0-13-amd64".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn garbage_before_version() {
+        // A digit followed by any byte and a dot is not a version
+        for (s, v) in [
+            ("1X.bad 6.1.0-13-amd64", Some("6.1.0-13-amd64")),
+            ("1X.bad 5.4", Some("5.4")),
+            ("1X.bad", None),
+        ] {
+            let image = crate::helpers::make_linux_bzimage(s);
+            assert_eq!(
+                version(&mut std::io::Cursor::new(image), BinaryKind::X86Kernel)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+                "{}",
+                s
+            );
+        }
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    elf_utils::XZ_MAGIC, strings::IntoStringsIter, version, BinaryKind, FindVersionError,
+    VersionFinder,
+};
+use regex::Regex;
+use std::io::Cursor;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+pub(crate) struct XzPayload<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+}
+
+impl<'a, R: AsyncRead + Unpin> XzPayload<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        XzPayload { buf }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for XzPayload<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        // FIXME: Avoid reading the whole file
+        let mut buffer = Vec::new();
+        self.buf.read_to_end(&mut buffer).await?;
+        if !buffer.starts_with(XZ_MAGIC) {
+            return Ok(None);
+        }
+
+        let mut payload = Vec::new();
+        if compress_tools::tokio_support::uncompress_data(&buffer[..], &mut payload)
+            .await
+            .is_err()
+        {
+            return Ok(None);
+        }
+
+        if let Some(version) = version(&mut Cursor::new(&payload), BinaryKind::LinuxKernel).await? {
+            return Ok(Some(version));
+        }
+
+        // Plain kernel images (e.g: vmlinux) have no boot header, so look for
+        // the Linux banner instead.
+        let re = Regex::new(r"Linux version (?P<version>\S+)").unwrap();
+        for line in payload.into_strings_iter() {
+            if let Some(v) = re.captures(&line).and_then(|c| c.name("version")) {
+                return Ok(Some(v.as_str().to_string()));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("xzpayload/vmlinux.xz", Some("5.10.0-9-arm64")),
+            ("xzpayload/x86-bzImage.xz", Some("4.1.30-1-MANJARO")),
+            ("elf/x86_64-sections.o.xz", None),
+            ("linuxkernel/x86-bzImage", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::XzPayload)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+            );
+        }
+    }
+}
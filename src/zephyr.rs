@@ -0,0 +1,77 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{builder::Config, custom::Custom, FindVersionError, VersionFinder};
+use tokio::io::AsyncRead;
+
+/// Zephyr RTOS kernel version string, as embedded in the application
+/// binaries (e.g: "Zephyr 3.5.0") or in their exported configuration (e.g:
+/// `CONFIG_VERSION_STRING="3.6.0-rc2"`), whose `version` group is the kernel
+/// version.
+pub const ZEPHYR_VERSION_PATTERN: &str =
+    r#"(?:\bZephyr |\bCONFIG_VERSION_STRING="?)(?P<version>\d+\.\d+\.\d+(?:-rc\d+)?)"#;
+
+pub(crate) struct ZephyrRTOS<'a, R: AsyncRead + Unpin> {
+    buf: &'a mut R,
+    config: Config<'a>,
+}
+
+impl<'a, R: AsyncRead + Unpin> ZephyrRTOS<'a, R> {
+    pub(crate) fn from_reader(buf: &'a mut R) -> Self {
+        ZephyrRTOS {
+            buf,
+            config: Config::default(),
+        }
+    }
+
+    pub(crate) fn with_config(self, config: Config<'a>) -> Self {
+        ZephyrRTOS { config, ..self }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<'a, R: AsyncRead + Unpin> VersionFinder for ZephyrRTOS<'a, R> {
+    async fn get_version(&mut self) -> Result<Option<String>, FindVersionError> {
+        Custom::from_reader(self.buf, ZEPHYR_VERSION_PATTERN)
+            .with_config(self.config)
+            .with_group("version")
+            .get_version()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{version, BinaryKind};
+    use tokio::io::{AsyncRead, AsyncSeek};
+
+    async fn fixture(name: &str) -> impl AsyncRead + AsyncSeek {
+        use tokio::{fs::File, io::BufReader};
+
+        BufReader::new(
+            File::open(&format!("tests/fixtures/{}", name))
+                .await
+                .unwrap_or_else(|_| panic!("Couldn't open the fixture {}", name)),
+        )
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        for (f, v) in &[
+            ("zephyr/hello_world.elf", Some("3.5.0")),
+            ("zephyr/peripheral_hr.elf", Some("3.6.0-rc2")),
+            ("cortexm/firmware.bin", None),
+            ("uboot/arm-spl", None),
+        ] {
+            assert_eq!(
+                version(&mut fixture(f).await, BinaryKind::ZephyrRTOS)
+                    .await
+                    .unwrap(),
+                v.map(|v| v.to_string()),
+                "{} version mismatch",
+                f
+            );
+        }
+    }
+}
@@ -0,0 +1,34 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use find_binary_version::{version_batch, BinaryKind};
+use futures_util::StreamExt;
+use std::collections::HashMap;
+
+#[tokio::test]
+async fn uboot_fixtures() {
+    let mut files: Vec<_> = std::fs::read_dir("tests/fixtures/uboot")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    files.push("tests/fixtures/uboot/missing".into());
+
+    let versions: HashMap<_, _> = version_batch(files, BinaryKind::UBoot, 2)
+        .map(|(path, version)| {
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            (name, version.map_err(|e| e.to_string()))
+        })
+        .collect()
+        .await;
+    assert_eq!(versions.len(), 3);
+    assert_eq!(
+        versions["arm-spl"],
+        Ok(Some("2017.11+fslc+ga07698f".to_string()))
+    );
+    assert_eq!(
+        versions["arm-u-boot-dtb.img"],
+        Ok(Some("2019.04-00014-gc93ced78db".to_string()))
+    );
+    assert!(versions["missing"].is_err());
+}
@@ -0,0 +1,90 @@
+// Copyright (C) 2019-2021 O.S. Systems Sofware LTDA
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Synthetic firmware images, so tests do not depend on real firmware files
+//! taken from external sources.
+//!
+//! The images only hold the fields the finders look at, padded to the size of
+//! a small real image.
+
+// Size the images are padded to
+const IMAGE_SIZE: usize = 0x10000;
+
+fn put(image: &mut [u8], offset: usize, data: &[u8]) {
+    image[offset..offset + data.len()].copy_from_slice(data);
+}
+
+/// ARM Barebox image: the exception vectors, the "barebox" image magic at
+/// 0x0020 and the version string following its linker symbol name.
+pub(crate) fn make_barebox_image(version: &str) -> Vec<u8> {
+    let mut image = vec![0; IMAGE_SIZE];
+    for offset in (0..0x20).step_by(4) {
+        // b <reset>
+        put(&mut image, offset, &0xEA00_0006_u32.to_le_bytes());
+    }
+    put(&mut image, 0x20, b"barebox");
+    put(
+        &mut image,
+        0x8000,
+        format!(
+            "barebox_version_string\0barebox {} #1 Mon Jan 2 00:00:00 UTC 2023\0",
+            version
+        )
+        .as_bytes(),
+    );
+    image
+}
+
+/// U-Boot binary whose boot banner holds the version.
+pub(crate) fn make_uboot_image(version: &str) -> Vec<u8> {
+    let mut image = vec![0; IMAGE_SIZE];
+    put(&mut image, 0x00, &0xEA00_00B8_u32.to_le_bytes());
+    put(
+        &mut image,
+        0x8000,
+        format!("U-Boot {} (Jan 02 2023 - 00:00:00 +0000)\0", version).as_bytes(),
+    );
+    image
+}
+
+/// x86 bzImage with a 2.15 boot protocol header, loaded high and with a
+/// 64-bit entry point, pointing to its version string.
+pub(crate) fn make_linux_bzimage(version: &str) -> Vec<u8> {
+    let mut image = vec![0; IMAGE_SIZE];
+    image[0x01F1] = 4; // setup_sects
+    put(&mut image, 0x01FE, &0xAA55_u16.to_le_bytes()); // boot_flag
+    put(&mut image, 0x0202, b"HdrS");
+    put(&mut image, 0x0206, &0x020F_u16.to_le_bytes()); // version
+    put(&mut image, 0x020E, &0x0200_u16.to_le_bytes()); // kernel_version
+    image[0x0211] = 0x1; // loadflags: LOADED_HIGH
+    put(&mut image, 0x0236, &0x1_u16.to_le_bytes()); // xloadflags: XLF_KERNEL_64
+    put(
+        &mut image,
+        0x0400,
+        format!("{} (builder@buildhost) #1 SMP PREEMPT\0", version).as_bytes(),
+    );
+    image
+}
+
+/// ARM Linux uImage, named after the kernel version, with valid header and
+/// data checksums.
+pub(crate) fn make_linux_uimage(version: &str) -> Vec<u8> {
+    let data = vec![0; IMAGE_SIZE - 0x40];
+    let mut header = vec![0; 0x40];
+    put(&mut header, 0x00, &0x2705_1956_u32.to_be_bytes()); // ih_magic
+    put(&mut header, 0x08, &0x63B2_1E00_u32.to_be_bytes()); // ih_time
+    put(&mut header, 0x0C, &(data.len() as u32).to_be_bytes()); // ih_size
+    put(&mut header, 0x10, &0x1000_8000_u32.to_be_bytes()); // ih_load
+    put(&mut header, 0x14, &0x1000_8000_u32.to_be_bytes()); // ih_ep
+    put(&mut header, 0x18, &crc32fast::hash(&data).to_be_bytes()); // ih_dcrc
+    header[0x1C] = 5; // ih_os: Linux
+    header[0x1D] = 2; // ih_arch: ARM
+    header[0x1E] = 2; // ih_type: kernel
+    put(&mut header, 0x20, format!("Linux-{}", version).as_bytes());
+    let header_crc = crc32fast::hash(&header);
+    put(&mut header, 0x04, &header_crc.to_be_bytes()); // ih_hcrc
+
+    header.extend_from_slice(&data);
+    header
+}